@@ -0,0 +1,108 @@
+use lazy_static::*;
+
+use crate::color::{OkLab, ARGB, ARGB16};
+
+// The default colors of xterm. Terminal themes usually redefine these.
+const ANSI_16: [u32; 16] = [
+    0x000000, 0xcd0000, 0x00cd00, 0xcdcd00, 0x0000ee, 0xcd00cd, 0x00cdcd, 0xe5e5e5, 0x7f7f7f,
+    0xff0000, 0x00ff00, 0xffff00, 0x5c5cff, 0xff00ff, 0x00ffff, 0xffffff,
+];
+
+// Levels of the 6×6×6 color cube that occupies indices 16–231
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+lazy_static! {
+    static ref PALETTE: Vec<OkLab> = (0..=255).map(|n| OkLab::from_rgb(color(n))).collect();
+}
+
+/// The color of an index of the xterm 256 color palette.
+pub fn color(index: u8) -> ARGB {
+    let index = usize::from(index);
+    match index {
+        0..=15 => {
+            let rgb = ANSI_16[index];
+            ARGB::new(0xff, (rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+        }
+        16..=231 => {
+            let n = index - 16;
+            ARGB::new(
+                0xff,
+                CUBE_LEVELS[n / 36],
+                CUBE_LEVELS[n / 6 % 6],
+                CUBE_LEVELS[n % 6],
+            )
+        }
+        _ => {
+            let level = 8 + 10 * (index - 232) as u8;
+            ARGB::new(0xff, level, level, level)
+        }
+    }
+}
+
+fn nearest(color: ARGB16, indices: std::ops::RangeInclusive<u8>) -> u8 {
+    let target = OkLab::from_rgb(color);
+    indices
+        .map(|index| (index, PALETTE[usize::from(index)].distance(&target)))
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(index, _)| index)
+        .unwrap_or(0)
+}
+
+/// Finds the index of the 16 color palette that looks the most like `color`.
+pub fn nearest_16(color: ARGB16) -> u8 {
+    nearest(color, 0..=15)
+}
+
+/// Finds the index of the 256 color palette that looks the most like
+/// `color`. The first 16 colors are skipped since they depend on the terminal
+/// theme.
+pub fn nearest_256(color: ARGB16) -> u8 {
+    nearest(color, 16..=255)
+}
+
+/// The SGR parameter selecting an index of the 16 color palette as the
+/// foreground color.
+pub fn sgr_16(index: u8) -> u8 {
+    if index < 8 {
+        30 + index
+    } else {
+        90 + index - 8
+    }
+}
+
+#[test]
+fn test_color() {
+    assert_eq!(color(0), ARGB::BLACK);
+    assert_eq!(color(9), ARGB::new(0xff, 0xff, 0, 0));
+    assert_eq!(color(16), ARGB::BLACK);
+    assert_eq!(color(196), ARGB::new(0xff, 0xff, 0, 0));
+    assert_eq!(color(33), ARGB::new(0xff, 0, 0x87, 0xff));
+    assert_eq!(color(231), ARGB::WHITE);
+    assert_eq!(color(232), ARGB::new(0xff, 8, 8, 8));
+    assert_eq!(color(255), ARGB::new(0xff, 0xee, 0xee, 0xee));
+}
+
+#[test]
+fn test_nearest() {
+    let rgb = |r, g, b| ARGB16::from(ARGB::new(0xff, r, g, b));
+    for index in 16..=255 {
+        assert_eq!(nearest_256(color(index).into()), index);
+    }
+    for index in 0..=15 {
+        assert_eq!(nearest_16(color(index).into()), index);
+    }
+
+    assert_eq!(nearest_256(rgb(0xfe, 0x01, 0x01)), 196);
+    assert_eq!(nearest_256(rgb(0x1e, 0x90, 0xff)), 33);
+    assert_eq!(nearest_256(rgb(0x80, 0x80, 0x80)), 244);
+    assert_eq!(nearest_16(rgb(0xfe, 0x01, 0x01)), 9);
+    assert_eq!(nearest_16(rgb(0x80, 0x00, 0x00)), 1);
+}
+
+#[test]
+fn test_sgr_16() {
+    assert_eq!(sgr_16(0), 30);
+    assert_eq!(sgr_16(7), 37);
+    assert_eq!(sgr_16(8), 90);
+    assert_eq!(sgr_16(15), 97);
+}
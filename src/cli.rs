@@ -13,7 +13,14 @@ pub fn get_cli() -> App<'static, 'static> {
                 .takes_value(true)
                 .value_name("NAME")
                 .help("Output format (defaults to hex)")
-                .possible_values(&["hex", "HEX", "hex!", "HEX!", "plain", "rgb", "hsl"])
+                .possible_values(&[
+                    "hex", "HEX", "hex!", "HEX!", "hexa", "HEXA", "hexa!", "HEXA!", "ahex", "AHEX",
+                    "plain", "rgb", "rgba", "hsl", "hsv", "lab", "lch", "xyz", "oklab", "oklch",
+                    "cmyk", "hwb", "name", "apca",
+                    "protanopia", "deuteranopia", "tritanopia", "achromatopsia",
+                    "ansi16", "ansi256", "sgr16", "sgr256", "sgr24", "kelvin", "p3", "rec2020",
+                    "x11",
+                ])
                 .conflicts_with("custom"),
         )
         .arg(
@@ -25,6 +32,42 @@ pub fn get_cli() -> App<'static, 'static> {
                 .help("Custom output format")
                 .conflicts_with("format"),
         )
+        .arg(
+            Arg::with_name("black_generation")
+                .long("black-generation")
+                .takes_value(true)
+                .value_name("PERCENT")
+                .help("Share of gray replaced by black in CMYK output (defaults to 100)"),
+        )
+        .arg(
+            Arg::with_name("under_color_removal")
+                .long("under-color-removal")
+                .takes_value(true)
+                .value_name("PERCENT")
+                .help("Share of black removed from CMY in CMYK output (defaults to 100)"),
+        )
+        .arg(
+            Arg::with_name("compare")
+                .long("compare")
+                .takes_value(false)
+                .help("Pick two colors and print the difference between them"),
+        )
+        .arg(
+            Arg::with_name("palette")
+                .long("palette")
+                .takes_value(true)
+                .value_name("PALETTE")
+                .possible_values(&[
+                    "complementary",
+                    "analogous",
+                    "triadic",
+                    "split-complementary",
+                    "tints",
+                    "shades",
+                ])
+                .help("Print a palette generated from the picked color")
+                .conflicts_with("compare"),
+        )
         .arg(
             Arg::with_name("selection")
                 .short("s")
@@ -52,6 +95,22 @@ pub fn get_cli() -> App<'static, 'static> {
                 .value_name("PREVIEW_SIZE")
                 .help("Size of preview, must be odd (defaults to 255)"),
         )
+        .arg(
+            Arg::with_name("simulate")
+                .long("simulate")
+                .takes_value(true)
+                .value_name("DEFICIENCY")
+                .possible_values(&["protanopia", "deuteranopia", "tritanopia", "achromatopsia"])
+                .help("Show the preview as seen with a color vision deficiency"),
+        )
+        .arg(
+            Arg::with_name("display_space")
+                .long("display-space")
+                .takes_value(true)
+                .value_name("SPACE")
+                .possible_values(&["srgb", "display-p3", "rec2020", "icc"])
+                .help("Color space of the display (defaults to srgb)"),
+        )
         .arg(
             Arg::with_name("position")
                 .short("p")
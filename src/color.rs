@@ -1,9 +1,12 @@
-use anyhow::{anyhow, Result};
-use std;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Error, Result};
 use xcb::xproto;
 use xcb::Connection;
 
-#[derive(Clone, Copy, PartialEq)]
+use crate::visual::{self, PixelFormat};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub struct ARGB {
     pub a: u8,
     pub r: u8,
@@ -36,14 +39,17 @@ impl ARGB {
     }
 
     pub fn is_compactable(self) -> bool {
-        fn compact(n: u8) -> bool {
-            (n >> 4) == (n & 0xf)
-        }
-        compact(self.r) && compact(self.g) && compact(self.b)
+        is_compactable(self.r) && is_compactable(self.g) && is_compactable(self.b)
     }
 
+    pub fn is_compactable_with_alpha(self) -> bool {
+        self.is_compactable() && is_compactable(self.a)
+    }
+
+    /// Whether white text would be more readable on top of this color than
+    /// black text.
     pub fn is_dark(self) -> bool {
-        self.distance(Self::BLACK) < self.distance(Self::WHITE)
+        contrast_ratio(self, Self::WHITE) > contrast_ratio(self, Self::BLACK)
     }
 
     pub fn distance(self, other: ARGB) -> f32 {
@@ -53,16 +59,9 @@ impl ARGB {
         .sqrt()
     }
 
+    /// Mixes the color with `other`. See `ARGB16::interpolate`.
     pub fn interpolate(self, other: ARGB, amount: f32) -> ARGB {
-        fn lerp(a: u8, b: u8, x: f32) -> u8 {
-            ((1.0 - x) * f32::from(a) + x * f32::from(b)).ceil() as u8
-        }
-        ARGB {
-            a: self.a,
-            r: lerp(self.r, other.r, amount),
-            g: lerp(self.g, other.g, amount),
-            b: lerp(self.b, other.b, amount),
-        }
+        ARGB16::from(self).interpolate(other.into(), amount).into()
     }
 
     pub fn lighten(self, amount: f32) -> ARGB {
@@ -74,6 +73,10 @@ impl ARGB {
     }
 }
 
+fn is_compactable(n: u8) -> bool {
+    (n >> 4) == (n & 0xf)
+}
+
 impl From<ARGB> for u32 {
     fn from(color: ARGB) -> u32 {
         u32::from(color.a) << 24
@@ -83,11 +86,93 @@ impl From<ARGB> for u32 {
     }
 }
 
+/// A color with 16 bits per channel. Colors read from deep visuals are kept at
+/// this precision until they are formatted.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ARGB16 {
+    pub a: u16,
+    pub r: u16,
+    pub g: u16,
+    pub b: u16,
+}
+
+impl ARGB16 {
+    pub const fn new(a: u16, r: u16, g: u16, b: u16) -> ARGB16 {
+        ARGB16 { a, r, g, b }
+    }
+
+    /// Red, green and blue in the range 0–1.
+    fn normalized(self) -> [f32; 3] {
+        [
+            f32::from(self.r) / 65535.0,
+            f32::from(self.g) / 65535.0,
+            f32::from(self.b) / 65535.0,
+        ]
+    }
+
+    /// Mixes the color with `other`, `amount` being the share of `other` in the
+    /// range 0–1. Colors are mixed in OKLab, so evenly spaced amounts give
+    /// perceptually even steps. Mixed colors that fall outside of the sRGB
+    /// gamut have their chroma reduced. The alpha of the color is kept.
+    pub fn interpolate(self, other: ARGB16, amount: f32) -> ARGB16 {
+        let mixed = OkLab::from_rgb(self).interpolate(OkLab::from_rgb(other), amount);
+        ARGB16 {
+            a: self.a,
+            ..OkLCh::from_oklab(mixed).to_rgb()
+        }
+    }
+
+    pub fn lighten(self, amount: f32) -> ARGB16 {
+        self.interpolate(ARGB::WHITE.into(), amount)
+    }
+
+    pub fn darken(self, amount: f32) -> ARGB16 {
+        self.interpolate(ARGB::BLACK.into(), amount)
+    }
+
+    /// Encodes linear-light sRGB components into a color. Components outside
+    /// the range 0–1 are clipped.
+    fn from_linear(a: u16, rgb: [f32; 3]) -> ARGB16 {
+        ARGB16::from_normalized(a, rgb.map(|c| linear_to_srgb(c.clamp(0.0, 1.0))))
+    }
+
+    /// The inverse of `normalized`. Components outside the range 0–1 are
+    /// clipped.
+    fn from_normalized(a: u16, rgb: [f32; 3]) -> ARGB16 {
+        let [r, g, b] = rgb.map(|c| (c.clamp(0.0, 1.0) * 65535.0).round() as u16);
+        ARGB16::new(a, r, g, b)
+    }
+}
+
+impl From<ARGB> for ARGB16 {
+    fn from(color: ARGB) -> ARGB16 {
+        let widen = |n: u8| u16::from(n) * 0x101;
+        ARGB16::new(
+            widen(color.a),
+            widen(color.r),
+            widen(color.g),
+            widen(color.b),
+        )
+    }
+}
+
+impl From<ARGB16> for ARGB {
+    fn from(color: ARGB16) -> ARGB {
+        let narrow = |n: u16| ((u32::from(n) * 0xff + 0x7fff) / 0xffff) as u8;
+        ARGB::new(
+            narrow(color.a),
+            narrow(color.r),
+            narrow(color.g),
+            narrow(color.b),
+        )
+    }
+}
+
 pub fn window_rect(
     conn: &Connection,
     window: xproto::Window,
     (x, y, width, height): (i16, i16, u16, u16),
-) -> Result<Vec<ARGB>> {
+) -> Result<Vec<ARGB16>> {
     let reply = xproto::get_image(
         conn,
         xproto::IMAGE_FORMAT_Z_PIXMAP as u8,
@@ -96,22 +181,41 @@ pub fn window_rect(
         y,
         width,
         height,
-        std::u32::MAX,
+        u32::MAX,
     )
     .get_reply()?;
 
-    if reply.depth() != 24 {
-        // TODO: Figure out what to do with these
-        return Err(anyhow!("Unsupported color depth"));
-    }
+    let format = PixelFormat::new(conn, reply.visual(), reply.depth())?;
+    let bytes_per_pixel = format.bytes_per_pixel();
 
     let data = reply.data();
-    let mut pixels = Vec::with_capacity(data.len());
-    for chunk in data.chunks(4) {
-        pixels.push(ARGB::new(0xff, chunk[2], chunk[1], chunk[0]));
+    let (width, height) = (usize::from(width), usize::from(height));
+    if width == 0 || height == 0 {
+        return Ok(Vec::new());
     }
+    let mut pixels = Vec::with_capacity(width * height);
 
-    Ok(pixels)
+    // Scanlines are padded, so rows can be longer than their pixels
+    let stride = data.len() / height;
+    for row in data.chunks(stride) {
+        for chunk in row[..width * bytes_per_pixel].chunks(bytes_per_pixel) {
+            pixels.push(format.read(chunk));
+        }
+    }
+
+    if format.is_indexed() {
+        let attributes = xproto::get_window_attributes(conn, window).get_reply()?;
+        let indices: Vec<u32> = pixels
+            .into_iter()
+            .map(|pixel| format.colormap_index(pixel))
+            .collect();
+        visual::query_colors(conn, attributes.colormap(), &indices)
+    } else {
+        Ok(pixels
+            .into_iter()
+            .map(|pixel| format.decode(pixel))
+            .collect())
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -121,41 +225,1039 @@ pub struct HSL {
     pub l: f32
 }
 
+/// Controls how much of the gray component shared by cyan, magenta and yellow
+/// is printed with black ink when separating a color into CMYK.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct InkSeparation {
+    /// Fraction of the gray component that is replaced by black ink.
+    pub black_generation: f32,
+    /// Fraction of the generated black that is removed from cyan, magenta and
+    /// yellow.
+    pub under_color_removal: f32,
+}
+
+impl InkSeparation {
+    /// Full black generation and under color removal. This is the naive
+    /// conversion most software performs, which also rescales the remaining
+    /// cyan, magenta and yellow to the ink left after removing black.
+    pub const NAIVE: InkSeparation = InkSeparation {
+        black_generation: 1.0,
+        under_color_removal: 1.0,
+    };
+}
+
+/// CMYK ink coverage with all channels in the range 0–100. This is the naive
+/// separation of sRGB without a printer profile, so the values depend on the
+/// device the inks are printed with.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CMYK {
+    pub c: f32,
+    pub m: f32,
+    pub y: f32,
+    pub k: f32,
+}
+
+impl CMYK {
+    pub fn from_rgb(rgb: impl Into<ARGB16>, separation: InkSeparation) -> CMYK {
+        let [r, g, b] = rgb.into().normalized();
+        let (c, m, y) = (1.0 - r, 1.0 - g, 1.0 - b);
+
+        let k = c.min(m).min(y) * separation.black_generation;
+        let removed = k * separation.under_color_removal;
+        let remove = |x: f32| {
+            if separation != InkSeparation::NAIVE {
+                x - removed
+            } else if removed < 1.0 {
+                (x - removed) / (1.0 - removed)
+            } else {
+                0.0
+            }
+        };
+
+        CMYK {
+            c: remove(c) * 100.0,
+            m: remove(m) * 100.0,
+            y: remove(y) * 100.0,
+            k: k * 100.0,
+        }
+    }
+}
+
+// Source: https://en.wikipedia.org/wiki/HSL_and_HSV#Hue_and_chroma
+fn hue(r: f32, g: f32, b: f32) -> f32 {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+
+    let h = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        (g - b) / delta + if g < b { 6.0 } else { 0.0 }
+    } else if max == g {
+        (b - r) / delta + 2.0
+    } else {
+        (r - g) / delta + 4.0
+    };
+
+    h * 60.0
+}
+
 impl HSL {
     // Source: https://en.wikipedia.org/wiki/HSL_and_HSV#From_RGB
-    pub fn from_rgb(rgb: ARGB) -> HSL {
-        let r: f32 = f32::from(rgb.r) / 255.0;
-        let g: f32 = f32::from(rgb.g) / 255.0;
-        let b: f32 = f32::from(rgb.b) / 255.0;
-        let max = vec![r, g, b].iter().cloned().fold(0.0/0.0, f32::max);
-        let min = vec![r, g, b].iter().cloned().fold(0.0/0.0, f32::min);
-        let mut h: f32 = 0.0;
-        let mut s: f32 = 0.0;
-        let l = ((max + min) / 2.0 * 100.0).round();
-
-        if max != min {
-            let delta = max - min;
-            s = if l > 50.0 {
-                ( delta / (2.0 - max - min) ) * 100.0
+    pub fn from_rgb(rgb: impl Into<ARGB16>) -> HSL {
+        let [r, g, b] = rgb.into().normalized();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let l = (max + min) / 2.0;
+
+        let s = if max == min {
+            0.0
+        } else if l > 0.5 {
+            (max - min) / (2.0 - max - min)
+        } else {
+            (max - min) / (max + min)
+        };
+
+        HSL {
+            h: hue(r, g, b),
+            s: s * 100.0,
+            l: l * 100.0,
+        }
+    }
+}
+
+// Source: https://www.w3.org/TR/css-color-4/#hsl-to-rgb
+impl From<HSL> for ARGB16 {
+    fn from(hsl: HSL) -> ARGB16 {
+        let (s, l) = (hsl.s / 100.0, hsl.l / 100.0);
+        let a = s * l.min(1.0 - l);
+        let f = |n: f32| {
+            let k = (n + hsl.h / 30.0).rem_euclid(12.0);
+            l - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+        };
+        ARGB16::from_normalized(0xffff, [f(0.0), f(8.0), f(4.0)])
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct HSV {
+    pub h: f32,
+    pub s: f32,
+    pub v: f32,
+}
+
+impl HSV {
+    // Source: https://en.wikipedia.org/wiki/HSL_and_HSV#From_RGB
+    pub fn from_rgb(rgb: impl Into<ARGB16>) -> HSV {
+        let [r, g, b] = rgb.into().normalized();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+
+        let s = if max == 0.0 { 0.0 } else { (max - min) / max };
+
+        HSV {
+            h: hue(r, g, b),
+            s: s * 100.0,
+            v: max * 100.0,
+        }
+    }
+}
+
+// Source: https://en.wikipedia.org/wiki/HSL_and_HSV#HSV_to_RGB_alternative
+impl From<HSV> for ARGB16 {
+    fn from(hsv: HSV) -> ARGB16 {
+        let (s, v) = (hsv.s / 100.0, hsv.v / 100.0);
+        let f = |n: f32| {
+            let k = (n + hsv.h / 60.0).rem_euclid(6.0);
+            v - v * s * k.min(4.0 - k).clamp(0.0, 1.0)
+        };
+        ARGB16::from_normalized(0xffff, [f(5.0), f(3.0), f(1.0)])
+    }
+}
+
+/// Hue, whiteness and blackness as used by CSS `hwb()`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct HWB {
+    pub h: f32,
+    pub w: f32,
+    pub b: f32,
+}
+
+impl HWB {
+    // Source: https://www.w3.org/TR/css-color-4/#hwb-to-rgb
+    pub fn from_hsv(hsv: HSV) -> HWB {
+        HWB {
+            h: hsv.h,
+            w: (100.0 - hsv.s) * hsv.v / 100.0,
+            b: 100.0 - hsv.v,
+        }
+    }
+
+    pub fn from_rgb(rgb: impl Into<ARGB16>) -> HWB {
+        HWB::from_hsv(HSV::from_rgb(rgb))
+    }
+
+    /// The inverse of `from_hsv`. Whiteness and blackness that add up to more
+    /// than 100 are scaled down proportionally, which gives a gray.
+    pub fn to_hsv(self) -> HSV {
+        let scale = (self.w + self.b).max(100.0) / 100.0;
+        let (w, b) = (self.w / scale, self.b / scale);
+        let v = 100.0 - b;
+        HSV {
+            h: self.h,
+            s: if v == 0.0 { 0.0 } else { 100.0 - w / v * 100.0 },
+            v,
+        }
+    }
+}
+
+impl From<HWB> for ARGB16 {
+    fn from(hwb: HWB) -> ARGB16 {
+        ARGB16::from(hwb.to_hsv())
+    }
+}
+
+/// Decodes a gamma-encoded sRGB component in the range 0–1 into linear light.
+// Source: https://www.w3.org/TR/css-color-4/#color-conversion-code
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encodes a linear-light sRGB component in the range 0–1 with the sRGB
+/// transfer function.
+// Source: https://www.w3.org/TR/css-color-4/#color-conversion-code
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Linear-light sRGB components of a color in the range 0–1.
+fn linear_rgb(rgb: ARGB16) -> [f32; 3] {
+    rgb.normalized().map(srgb_to_linear)
+}
+
+fn mul3(m: &[[f32; 3]; 3], v: [f32; 3]) -> [f32; 3] {
+    [
+        m[0][0] * v[0] + m[0][1] * v[1] + m[0][2] * v[2],
+        m[1][0] * v[0] + m[1][1] * v[1] + m[1][2] * v[2],
+        m[2][0] * v[0] + m[2][1] * v[1] + m[2][2] * v[2],
+    ]
+}
+
+// Inverts a matrix by its adjugate, or returns `None` if it is singular
+fn invert3(m: &[[f32; 3]; 3]) -> Option<[[f32; 3]; 3]> {
+    let cofactor =
+        |r0: usize, r1: usize, c0: usize, c1: usize| m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+    let adjugate = [
+        [
+            cofactor(1, 2, 1, 2),
+            -cofactor(0, 2, 1, 2),
+            cofactor(0, 1, 1, 2),
+        ],
+        [
+            -cofactor(1, 2, 0, 2),
+            cofactor(0, 2, 0, 2),
+            -cofactor(0, 1, 0, 2),
+        ],
+        [
+            cofactor(1, 2, 0, 1),
+            -cofactor(0, 2, 0, 1),
+            cofactor(0, 1, 0, 1),
+        ],
+    ];
+    let determinant =
+        m[0][0] * adjugate[0][0] + m[0][1] * adjugate[1][0] + m[0][2] * adjugate[2][0];
+    if determinant.abs() < 1e-9 {
+        return None;
+    }
+    Some(adjugate.map(|row| row.map(|n| n / determinant)))
+}
+
+// Source: https://www.w3.org/TR/css-color-4/#color-conversion-code
+const LINEAR_SRGB_TO_XYZ: [[f32; 3]; 3] = [
+    [0.412_390_8, 0.357_584_33, 0.180_480_8],
+    [0.212_639, 0.715_168_66, 0.072_192_32],
+    [0.019_330_82, 0.119_194_78, 0.950_532_15],
+];
+
+/// CIE XYZ tristimulus values relative to the D65 white point, scaled so that
+/// `y` is 1 for the sRGB white.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct XYZ {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl XYZ {
+    pub const D65: XYZ = XYZ {
+        x: 0.3127 / 0.3290,
+        y: 1.0,
+        z: (1.0 - 0.3127 - 0.3290) / 0.3290,
+    };
+
+    pub fn from_rgb(rgb: impl Into<ARGB16>) -> XYZ {
+        let [x, y, z] = mul3(&LINEAR_SRGB_TO_XYZ, linear_rgb(rgb.into()));
+        XYZ { x, y, z }
+    }
+
+    /// Adapts a color relative to the D50 white point into D65 with the
+    /// Bradford transform.
+    // Source: http://www.brucelindbloom.com/index.html?Eqn_ChromAdapt.html
+    pub fn from_d50(xyz: XYZ) -> XYZ {
+        const BRADFORD_D50_TO_D65: [[f32; 3]; 3] = [
+            [0.955_576_6, -0.023_039_3, 0.063_163_6],
+            [-0.028_289_5, 1.009_941_6, 0.021_007_7],
+            [0.012_298_2, -0.020_483, 1.329_909_8],
+        ];
+
+        let [x, y, z] = mul3(&BRADFORD_D50_TO_D65, [xyz.x, xyz.y, xyz.z]);
+        XYZ { x, y, z }
+    }
+}
+
+/// The correlated color temperature (CCT) of a color in Kelvin and its signed
+/// distance from the Planckian locus in the CIE 1960 UCS (Duv). Positive Duv
+/// values are greenish and negative ones pinkish. The temperature is only
+/// meaningful for colors close to the locus, roughly when `duv` is within
+/// ±0.05, and is limited to the range 1000–15000 K.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CCT {
+    pub kelvin: f32,
+    pub duv: f32,
+}
+
+// Chromaticity of a black body radiator in the CIE 1960 UCS
+// Source: M. Krystek, "An algorithm to calculate correlated colour temperature",
+// Color Research & Application, 10(1), 1985
+fn planckian_uv(kelvin: f32) -> (f32, f32) {
+    let t = kelvin;
+    let u = (0.860_117_76 + 1.541_182_5e-4 * t + 1.286_412e-7 * t * t)
+        / (1.0 + 8.424_202e-4 * t + 7.081_452e-7 * t * t);
+    let v = (0.317_398_73 + 4.228_062_5e-5 * t + 4.204_817e-8 * t * t)
+        / (1.0 - 2.897_418_2e-5 * t + 1.614_560_5e-7 * t * t);
+    (u, v)
+}
+
+impl CCT {
+    pub fn from_xyz(xyz: XYZ) -> CCT {
+        // Temperatures are searched in mireds, in which the locus is more evenly
+        // spaced than in Kelvin
+        const MIN_MIRED: f32 = 1e6 / 15000.0;
+        const MAX_MIRED: f32 = 1e6 / 1000.0;
+
+        // Black has no chromaticity, so treat it like the other neutral colors
+        let denominator = xyz.x + 15.0 * xyz.y + 3.0 * xyz.z;
+        let xyz = if denominator > 0.0 { xyz } else { XYZ::D65 };
+        let denominator = xyz.x + 15.0 * xyz.y + 3.0 * xyz.z;
+        let (u, v) = (4.0 * xyz.x / denominator, 6.0 * xyz.y / denominator);
+
+        let distance = |mired: f32| {
+            let (locus_u, locus_v) = planckian_uv(1e6 / mired);
+            (u - locus_u).hypot(v - locus_v)
+        };
+
+        // Find the closest whole mired, then refine it with a golden section
+        // search
+        let mut closest = MIN_MIRED;
+        let mut mired = MIN_MIRED;
+        while mired <= MAX_MIRED {
+            if distance(mired) < distance(closest) {
+                closest = mired;
+            }
+            mired += 1.0;
+        }
+        let (mut min, mut max) = (
+            (closest - 1.0).max(MIN_MIRED),
+            (closest + 1.0).min(MAX_MIRED),
+        );
+        let ratio = (5.0f32.sqrt() - 1.0) / 2.0;
+        while max - min > 1e-3 {
+            let a = max - ratio * (max - min);
+            let b = min + ratio * (max - min);
+            if distance(a) < distance(b) {
+                max = b;
             } else {
-                ( delta / (max + min) ) * 100.0
-            };
-
-            if max == r {
-                h = (g - b) / delta + if g < b { 6.0 } else { 0.0 };
-            } else if max == g {
-                h = (b - r) / delta + 2.0
-            } else if max == b {
-                h = (r - g) / delta + 4.0
+                min = a;
             }
+        }
+
+        let kelvin = 1e6 / ((min + max) / 2.0);
+        let (locus_u, locus_v) = planckian_uv(kelvin);
+        let duv = (u - locus_u).hypot(v - locus_v).copysign(v - locus_v);
+        CCT { kelvin, duv }
+    }
+
+    #[cfg(test)]
+    pub fn from_rgb(rgb: impl Into<ARGB16>) -> CCT {
+        CCT::from_xyz(XYZ::from_rgb(rgb))
+    }
+}
 
-            h = h * 60.0;
+/// The relative luminance of a color in the range 0–1 as defined by WCAG 2.x.
+// Source: https://www.w3.org/TR/WCAG21/#dfn-relative-luminance
+pub fn relative_luminance(rgb: impl Into<ARGB16>) -> f32 {
+    let [r, g, b] = linear_rgb(rgb.into());
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+/// The WCAG 2.x contrast ratio between two colors, from 1 to 21. The order of
+/// the colors does not matter.
+// Source: https://www.w3.org/TR/WCAG21/#dfn-contrast-ratio
+pub fn contrast_ratio(a: impl Into<ARGB16>, b: impl Into<ARGB16>) -> f32 {
+    let (a, b) = (relative_luminance(a), relative_luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+/// The APCA lightness contrast (Lc) of text on a background, roughly from -108
+/// to 106. Dark text on a light background gives positive values and light
+/// text on a dark background negative ones.
+// Source: https://github.com/Myndex/apca-w3 (APCA-W3 0.0.98G-4g)
+pub fn apca_contrast(text: impl Into<ARGB16>, background: impl Into<ARGB16>) -> f32 {
+    const BLACK_THRESHOLD: f32 = 0.022;
+    const BLACK_CLAMP: f32 = 1.414;
+    const DELTA_Y_MIN: f32 = 0.0005;
+    const SCALE: f32 = 1.14;
+    const LOW_CLIP: f32 = 0.1;
+    const LOW_OFFSET: f32 = 0.027;
 
-            h = h.round();
-            s = s.round();
+    let luminance = |rgb: ARGB16| {
+        let [r, g, b] = rgb.normalized().map(|c| c.powf(2.4));
+        let y = 0.212_672_9 * r + 0.715_152_2 * g + 0.072_175 * b;
+        // Soft clamp near black to account for flare
+        if y > BLACK_THRESHOLD {
+            y
+        } else {
+            y + (BLACK_THRESHOLD - y).powf(BLACK_CLAMP)
         }
+    };
+    let text = luminance(text.into());
+    let background = luminance(background.into());
 
-        HSL { h, s, l }
+    if (background - text).abs() < DELTA_Y_MIN {
+        return 0.0;
+    }
+
+    let contrast = if background > text {
+        let sapc = (background.powf(0.56) - text.powf(0.57)) * SCALE;
+        if sapc < LOW_CLIP {
+            0.0
+        } else {
+            sapc - LOW_OFFSET
+        }
+    } else {
+        let sapc = (background.powf(0.65) - text.powf(0.62)) * SCALE;
+        if sapc > -LOW_CLIP {
+            0.0
+        } else {
+            sapc + LOW_OFFSET
+        }
+    };
+    contrast * 100.0
+}
+
+/// CIELAB under the D65 white point. `l` is in the range 0–100.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Lab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+impl Lab {
+    // Source: http://www.brucelindbloom.com/index.html?Eqn_XYZ_to_Lab.html
+    pub fn from_xyz(xyz: XYZ) -> Lab {
+        const EPSILON: f32 = 216.0 / 24389.0;
+        const KAPPA: f32 = 24389.0 / 27.0;
+
+        fn f(t: f32) -> f32 {
+            if t > EPSILON {
+                t.cbrt()
+            } else {
+                (KAPPA * t + 16.0) / 116.0
+            }
+        }
+
+        let fx = f(xyz.x / XYZ::D65.x);
+        let fy = f(xyz.y / XYZ::D65.y);
+        let fz = f(xyz.z / XYZ::D65.z);
+
+        Lab {
+            l: 116.0 * fy - 16.0,
+            a: 500.0 * (fx - fy),
+            b: 200.0 * (fy - fz),
+        }
+    }
+
+    pub fn from_rgb(rgb: impl Into<ARGB16>) -> Lab {
+        Lab::from_xyz(XYZ::from_rgb(rgb))
+    }
+
+    // Source: http://www.brucelindbloom.com/index.html?Eqn_Lab_to_XYZ.html
+    pub fn to_xyz(self) -> XYZ {
+        const EPSILON: f32 = 216.0 / 24389.0;
+        const KAPPA: f32 = 24389.0 / 27.0;
+
+        fn f_inv(t: f32) -> f32 {
+            if t.powi(3) > EPSILON {
+                t.powi(3)
+            } else {
+                (116.0 * t - 16.0) / KAPPA
+            }
+        }
+
+        let fy = (self.l + 16.0) / 116.0;
+        let fx = fy + self.a / 500.0;
+        let fz = fy - self.b / 200.0;
+        let y = if self.l > KAPPA * EPSILON {
+            fy.powi(3)
+        } else {
+            self.l / KAPPA
+        };
+
+        XYZ {
+            x: f_inv(fx) * XYZ::D65.x,
+            y: y * XYZ::D65.y,
+            z: f_inv(fz) * XYZ::D65.z,
+        }
+    }
+
+    /// The CIE76 color difference, which is the Euclidean distance in CIELAB.
+    pub fn delta_e76(&self, other: &Lab) -> f32 {
+        ((self.l - other.l).powi(2) + (self.a - other.a).powi(2) + (self.b - other.b).powi(2))
+            .sqrt()
+    }
+
+    /// The CIE94 color difference with the graphic arts weights. Unlike the
+    /// other differences, this one is not symmetric: `self` is the reference
+    /// color.
+    pub fn delta_e94(&self, other: &Lab) -> f32 {
+        const K1: f32 = 0.045;
+        const K2: f32 = 0.015;
+
+        let c1 = self.a.hypot(self.b);
+        let c2 = other.a.hypot(other.b);
+        let delta_l = self.l - other.l;
+        let delta_c = c1 - c2;
+        let delta_h_squared =
+            ((self.a - other.a).powi(2) + (self.b - other.b).powi(2) - delta_c.powi(2)).max(0.0);
+
+        let sc = 1.0 + K1 * c1;
+        let sh = 1.0 + K2 * c1;
+        (delta_l.powi(2) + (delta_c / sc).powi(2) + delta_h_squared / sh.powi(2)).sqrt()
+    }
+
+    /// The CIEDE2000 color difference.
+    // Source: G. Sharma, W. Wu and E. N. Dalal, "The CIEDE2000 color-difference
+    // formula: Implementation notes, supplementary test data, and mathematical
+    // observations", Color Research & Application, 30(1), 2005
+    pub fn delta_e2000(&self, other: &Lab) -> f32 {
+        const POW_25_7: f32 = 6_103_515_625.0;
+
+        let c_mean = (self.a.hypot(self.b) + other.a.hypot(other.b)) / 2.0;
+        let g = 0.5 * (1.0 - (c_mean.powi(7) / (c_mean.powi(7) + POW_25_7)).sqrt());
+        let (c1, h1) = polar((1.0 + g) * self.a, self.b, 0.0);
+        let (c2, h2) = polar((1.0 + g) * other.a, other.b, 0.0);
+
+        let delta_l = other.l - self.l;
+        let delta_c = c2 - c1;
+        let delta_h = if c1 * c2 == 0.0 {
+            0.0
+        } else if h2 - h1 > 180.0 {
+            h2 - h1 - 360.0
+        } else if h2 - h1 < -180.0 {
+            h2 - h1 + 360.0
+        } else {
+            h2 - h1
+        };
+        let delta_h = 2.0 * (c1 * c2).sqrt() * (delta_h / 2.0).to_radians().sin();
+
+        let l_mean = (self.l + other.l) / 2.0;
+        let c_mean = (c1 + c2) / 2.0;
+        let h_mean = if c1 * c2 == 0.0 {
+            h1 + h2
+        } else if (h1 - h2).abs() <= 180.0 {
+            (h1 + h2) / 2.0
+        } else if h1 + h2 < 360.0 {
+            (h1 + h2 + 360.0) / 2.0
+        } else {
+            (h1 + h2 - 360.0) / 2.0
+        };
+
+        let cos = |degrees: f32| degrees.to_radians().cos();
+        let t = 1.0 - 0.17 * cos(h_mean - 30.0)
+            + 0.24 * cos(2.0 * h_mean)
+            + 0.32 * cos(3.0 * h_mean + 6.0)
+            - 0.20 * cos(4.0 * h_mean - 63.0);
+        let delta_theta = 30.0 * (-((h_mean - 275.0) / 25.0).powi(2)).exp();
+        let rc = 2.0 * (c_mean.powi(7) / (c_mean.powi(7) + POW_25_7)).sqrt();
+        let sl = 1.0 + 0.015 * (l_mean - 50.0).powi(2) / (20.0 + (l_mean - 50.0).powi(2)).sqrt();
+        let sc = 1.0 + 0.045 * c_mean;
+        let sh = 1.0 + 0.015 * c_mean * t;
+        let rt = -(2.0 * delta_theta).to_radians().sin() * rc;
+
+        let (l, c, h) = (delta_l / sl, delta_c / sc, delta_h / sh);
+        (l * l + c * c + h * h + rt * c * h).sqrt()
+    }
+}
+
+/// Converts the color into opaque sRGB. Colors outside of the sRGB gamut are
+/// clipped.
+impl From<Lab> for ARGB16 {
+    fn from(lab: Lab) -> ARGB16 {
+        let XYZ { x, y, z } = lab.to_xyz();
+        ARGB16::from_linear(0xffff, mul3(&ColorSpace::SRGB.xyz_to_rgb, [x, y, z]))
+    }
+}
+
+/// Converts rectangular `a` and `b` coordinates into chroma and hue (in
+/// degrees). The hue of colors with chroma below `threshold` is meaningless and
+/// is reported as zero.
+fn polar(a: f32, b: f32, threshold: f32) -> (f32, f32) {
+    let c = a.hypot(b);
+    if c < threshold {
+        (c, 0.0)
+    } else {
+        (c, b.atan2(a).to_degrees().rem_euclid(360.0))
+    }
+}
+
+/// The cylindrical form of CIELAB.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct LCh {
+    pub l: f32,
+    pub c: f32,
+    pub h: f32,
+}
+
+impl LCh {
+    pub fn from_lab(lab: Lab) -> LCh {
+        let (c, h) = polar(lab.a, lab.b, 1e-3);
+        LCh { l: lab.l, c, h }
+    }
+
+    #[cfg(test)]
+    pub fn from_rgb(rgb: impl Into<ARGB16>) -> LCh {
+        LCh::from_lab(Lab::from_rgb(rgb))
+    }
+}
+
+/// Björn Ottosson's OKLab. `l` is in the range 0–1.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct OkLab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+impl OkLab {
+    // Source: https://bottosson.github.io/posts/oklab/
+    pub fn from_rgb(rgb: impl Into<ARGB16>) -> OkLab {
+        const LINEAR_SRGB_TO_LMS: [[f32; 3]; 3] = [
+            [0.412_221_46, 0.536_332_55, 0.051_445_995],
+            [0.211_903_5, 0.680_699_5, 0.107_396_96],
+            [0.088_302_46, 0.281_718_85, 0.629_978_7],
+        ];
+        const LMS_TO_OKLAB: [[f32; 3]; 3] = [
+            [0.210_454_26, 0.793_617_8, -0.004_072_047],
+            [1.977_998_5, -2.428_592_2, 0.450_593_7],
+            [0.025_904_037, 0.782_771_77, -0.808_675_77],
+        ];
+
+        let [l, m, s] = mul3(&LINEAR_SRGB_TO_LMS, linear_rgb(rgb.into()));
+        let [l, a, b] = mul3(&LMS_TO_OKLAB, [l.cbrt(), m.cbrt(), s.cbrt()]);
+        OkLab { l, a, b }
+    }
+
+    pub fn from_xyz(xyz: XYZ) -> OkLab {
+        const XYZ_TO_LMS: [[f32; 3]; 3] = [
+            [0.819_022_4, 0.361_906_25, -0.128_873_78],
+            [0.032_983_67, 0.929_286_9, 0.036_144_666],
+            [0.048_177_2, 0.264_239_54, 0.633_547_8],
+        ];
+        const LMS_TO_OKLAB: [[f32; 3]; 3] = [
+            [0.210_454_26, 0.793_617_8, -0.004_072_047],
+            [1.977_998_5, -2.428_592_2, 0.450_593_7],
+            [0.025_904_037, 0.782_771_77, -0.808_675_77],
+        ];
+
+        let [l, m, s] = mul3(&XYZ_TO_LMS, [xyz.x, xyz.y, xyz.z]);
+        let [l, a, b] = mul3(&LMS_TO_OKLAB, [l.cbrt(), m.cbrt(), s.cbrt()]);
+        OkLab { l, a, b }
+    }
+
+    pub fn to_xyz(self) -> XYZ {
+        let [x, y, z] = mul3(&LINEAR_SRGB_TO_XYZ, self.to_linear());
+        XYZ { x, y, z }
+    }
+
+    /// Linear-light sRGB components of the color. Components of colors outside
+    /// of the sRGB gamut are outside the range 0–1.
+    fn to_linear(self) -> [f32; 3] {
+        const OKLAB_TO_LMS: [[f32; 3]; 3] = [
+            [1.0, 0.396_337_78, 0.215_803_76],
+            [1.0, -0.105_561_35, -0.063_854_17],
+            [1.0, -0.089_484_18, -1.291_485_5],
+        ];
+        const LMS_TO_LINEAR_SRGB: [[f32; 3]; 3] = [
+            [4.076_741_7, -3.307_711_6, 0.230_969_93],
+            [-1.268_438, 2.609_757_4, -0.341_319_4],
+            [-0.004_196_086_3, -0.703_418_6, 1.707_614_7],
+        ];
+
+        let [l, m, s] = mul3(&OKLAB_TO_LMS, [self.l, self.a, self.b]);
+        mul3(&LMS_TO_LINEAR_SRGB, [l.powi(3), m.powi(3), s.powi(3)])
+    }
+
+    /// Converts the color into opaque sRGB. Colors outside of the sRGB gamut are
+    /// clipped.
+    pub fn to_rgb(self) -> ARGB16 {
+        ARGB16::from_linear(0xffff, self.to_linear())
+    }
+
+    /// Mixes the color with `other`, `amount` being the share of `other` in the
+    /// range 0–1.
+    pub fn interpolate(self, other: OkLab, amount: f32) -> OkLab {
+        let lerp = |a: f32, b: f32| a + (b - a) * amount;
+        OkLab {
+            l: lerp(self.l, other.l),
+            a: lerp(self.a, other.a),
+            b: lerp(self.b, other.b),
+        }
+    }
+
+    /// The Euclidean distance between two colors, also known as ΔEOK.
+    pub fn distance(&self, other: &OkLab) -> f32 {
+        ((self.l - other.l).powi(2) + (self.a - other.a).powi(2) + (self.b - other.b).powi(2))
+            .sqrt()
+    }
+}
+
+/// The cylindrical form of OKLab.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct OkLCh {
+    pub l: f32,
+    pub c: f32,
+    pub h: f32,
+}
+
+impl OkLCh {
+    pub fn from_oklab(oklab: OkLab) -> OkLCh {
+        let (c, h) = polar(oklab.a, oklab.b, 1e-5);
+        OkLCh { l: oklab.l, c, h }
+    }
+
+    #[cfg(test)]
+    pub fn from_rgb(rgb: impl Into<ARGB16>) -> OkLCh {
+        OkLCh::from_oklab(OkLab::from_rgb(rgb))
+    }
+
+    pub fn to_oklab(self) -> OkLab {
+        let h = self.h.to_radians();
+        OkLab {
+            l: self.l,
+            a: self.c * h.cos(),
+            b: self.c * h.sin(),
+        }
+    }
+
+    /// Converts the color into opaque sRGB. Colors outside of the sRGB gamut
+    /// have their chroma reduced until they fit, which keeps their lightness
+    /// and hue.
+    pub fn to_rgb(self) -> ARGB16 {
+        self.fit(|color| in_gamut(color.to_oklab().to_linear()))
+            .to_oklab()
+            .to_rgb()
+    }
+
+    // Reduces the chroma of the color until `in_gamut` accepts it. Colors that
+    // are as light as white or as dark as black are left alone.
+    // Source: https://www.w3.org/TR/css-color-4/#binsearch
+    fn fit(self, in_gamut: impl Fn(OkLCh) -> bool) -> OkLCh {
+        if self.l >= 1.0 || self.l <= 0.0 || in_gamut(self) {
+            return self;
+        }
+
+        let (mut min, mut max) = (0.0, self.c);
+        while max - min > GAMUT_EPSILON {
+            let c = (min + max) / 2.0;
+            if in_gamut(OkLCh { c, ..self }) {
+                min = c;
+            } else {
+                max = c;
+            }
+        }
+        OkLCh { c: min, ..self }
+    }
+}
+
+// Tolerance of the gamut checks, which absorbs the rounding errors of the
+// conversions
+const GAMUT_EPSILON: f32 = 1e-4;
+
+/// Whether linear-light components are within the range 0–1.
+fn in_gamut(linear: [f32; 3]) -> bool {
+    let range = -GAMUT_EPSILON..=1.0 + GAMUT_EPSILON;
+    linear.iter().all(|c| range.contains(c))
+}
+
+/// Transfer functions that decode the components of an RGB color space into
+/// linear light.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Transfer {
+    Srgb,
+    Rec2020,
+    /// The ICC parametric curve `(a·x + b)^g + e` above `d` and `c·x + f`
+    /// below it, with the parameters in the order g, a, b, c, d, e, f.
+    Parametric([f32; 7]),
+    /// Evenly spaced samples of the curve between 0 and 1, which are
+    /// interpolated linearly. The samples have to be increasing.
+    Table(Vec<f32>),
+}
+
+// Source: https://www.w3.org/TR/css-color-4/#color-conversion-code
+const REC2020_ALPHA: f32 = 1.099_296_8;
+const REC2020_BETA: f32 = 0.018_053_97;
+
+impl Transfer {
+    fn decode(&self, c: f32) -> f32 {
+        match self {
+            Transfer::Srgb => srgb_to_linear(c),
+            Transfer::Rec2020 if c < REC2020_BETA * 4.5 => c / 4.5,
+            Transfer::Rec2020 => ((c + REC2020_ALPHA - 1.0) / REC2020_ALPHA).powf(1.0 / 0.45),
+            Transfer::Parametric([g, a, b, c_, d, e, f]) => {
+                let linear = if c >= *d {
+                    (a * c + b).max(0.0).powf(*g) + e
+                } else {
+                    c_ * c + f
+                };
+                linear.clamp(0.0, 1.0)
+            }
+            Transfer::Table(samples) => {
+                let position = c.clamp(0.0, 1.0) * (samples.len() - 1) as f32;
+                let index = (position as usize).min(samples.len().saturating_sub(2));
+                let next = samples.get(index + 1).unwrap_or(&samples[index]);
+                let fraction = position - index as f32;
+                samples[index] + (next - samples[index]) * fraction
+            }
+        }
+    }
+
+    fn encode(&self, c: f32) -> f32 {
+        match self {
+            Transfer::Srgb => linear_to_srgb(c),
+            Transfer::Rec2020 if c < REC2020_BETA => c * 4.5,
+            Transfer::Rec2020 => REC2020_ALPHA * c.powf(0.45) - (REC2020_ALPHA - 1.0),
+            Transfer::Parametric([g, a, b, c_, d, e, f]) => {
+                let encoded = if c >= (a * d + b).max(0.0).powf(*g) + e {
+                    ((c - e).max(0.0).powf(1.0 / g) - b) / a
+                } else if *c_ != 0.0 {
+                    (c - f) / c_
+                } else {
+                    *d
+                };
+                encoded.clamp(0.0, 1.0)
+            }
+            Transfer::Table(samples) => {
+                let last = samples.len() - 1;
+                let index = samples[1..]
+                    .partition_point(|&sample| sample < c)
+                    .min(last - 1);
+                let (low, high) = (samples[index], samples[index + 1]);
+                let fraction = if high > low {
+                    ((c - low) / (high - low)).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                (index as f32 + fraction) / last as f32
+            }
+        }
+    }
+}
+
+/// An RGB color space with the D65 white point. Colors of the space are stored
+/// as `ARGB16` with encoded components.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ColorSpace {
+    rgb_to_xyz: [[f32; 3]; 3],
+    xyz_to_rgb: [[f32; 3]; 3],
+    transfers: [Transfer; 3],
+}
+
+// Source: https://www.w3.org/TR/css-color-4/#color-conversion-code
+impl ColorSpace {
+    pub const SRGB: ColorSpace = ColorSpace {
+        rgb_to_xyz: LINEAR_SRGB_TO_XYZ,
+        xyz_to_rgb: [
+            [3.240_97, -1.537_383_2, -0.498_610_76],
+            [-0.969_243_6, 1.875_967_5, 0.041_555_06],
+            [0.055_630_08, -0.203_976_96, 1.056_971_5],
+        ],
+        transfers: [Transfer::Srgb, Transfer::Srgb, Transfer::Srgb],
+    };
+
+    pub const DISPLAY_P3: ColorSpace = ColorSpace {
+        rgb_to_xyz: [
+            [0.486_570_95, 0.265_667_7, 0.198_217_29],
+            [0.228_974_56, 0.691_738_5, 0.079_286_91],
+            [0.0, 0.045_113_38, 1.043_944_4],
+        ],
+        xyz_to_rgb: [
+            [2.493_497, -0.931_383_6, -0.402_710_8],
+            [-0.829_489, 1.762_664_1, 0.023_624_686],
+            [0.035_845_83, -0.076_172_39, 0.956_884_5],
+        ],
+        transfers: [Transfer::Srgb, Transfer::Srgb, Transfer::Srgb],
+    };
+
+    pub const REC2020: ColorSpace = ColorSpace {
+        rgb_to_xyz: [
+            [0.636_958_05, 0.144_616_9, 0.168_880_98],
+            [0.262_700_2, 0.677_998_1, 0.059_301_716],
+            [0.0, 0.028_072_693, 1.060_985_1],
+        ],
+        xyz_to_rgb: [
+            [1.716_651_2, -0.355_670_8, -0.253_366_3],
+            [-0.666_684_4, 1.616_481_2, 0.015_768_546],
+            [0.017_639_857, -0.042_770_613, 0.942_103_1],
+        ],
+        transfers: [Transfer::Rec2020, Transfer::Rec2020, Transfer::Rec2020],
+    };
+
+    /// A color space with the given red, green, and blue primaries in XYZ and a
+    /// transfer function for each of the channels. Fails if the primaries do
+    /// not span a color space.
+    pub fn new(primaries: [XYZ; 3], transfers: [Transfer; 3]) -> Result<ColorSpace> {
+        let [r, g, b] = primaries;
+        let rgb_to_xyz = [[r.x, g.x, b.x], [r.y, g.y, b.y], [r.z, g.z, b.z]];
+        let xyz_to_rgb = invert3(&rgb_to_xyz).ok_or_else(|| anyhow!("Invalid primaries"))?;
+        Ok(ColorSpace {
+            rgb_to_xyz,
+            xyz_to_rgb,
+            transfers,
+        })
+    }
+
+    pub fn to_xyz(&self, rgb: ARGB16) -> XYZ {
+        let [r, g, b] = rgb.normalized();
+        let linear = [
+            self.transfers[0].decode(r),
+            self.transfers[1].decode(g),
+            self.transfers[2].decode(b),
+        ];
+        let [x, y, z] = mul3(&self.rgb_to_xyz, linear);
+        XYZ { x, y, z }
+    }
+
+    /// Encoded components of a color in the range 0–1. Colors outside of the
+    /// gamut of the space have their chroma reduced in OKLCH until they fit,
+    /// which keeps their lightness and hue.
+    pub fn encode(&self, xyz: XYZ) -> [f32; 3] {
+        let linear = |xyz: XYZ| mul3(&self.xyz_to_rgb, [xyz.x, xyz.y, xyz.z]);
+
+        let mut rgb = linear(xyz);
+        if !in_gamut(rgb) {
+            let fitted = OkLCh::from_oklab(OkLab::from_xyz(xyz))
+                .fit(|color| in_gamut(linear(color.to_oklab().to_xyz())));
+            rgb = linear(fitted.to_oklab().to_xyz());
+        }
+        let [r, g, b] = rgb.map(|c| c.clamp(0.0, 1.0));
+        [
+            self.transfers[0].encode(r),
+            self.transfers[1].encode(g),
+            self.transfers[2].encode(b),
+        ]
+    }
+
+    /// The color of the space with the given XYZ and alpha. See `encode`.
+    pub fn to_rgb(&self, xyz: XYZ, alpha: u16) -> ARGB16 {
+        ARGB16::from_normalized(alpha, self.encode(xyz))
+    }
+
+    /// Converts a color of the space into sRGB, reducing the chroma of colors
+    /// that sRGB cannot show.
+    pub fn to_srgb(&self, rgb: ARGB16) -> ARGB16 {
+        if *self == ColorSpace::SRGB {
+            return rgb;
+        }
+        ColorSpace::SRGB.to_rgb(self.to_xyz(rgb), rgb.a)
+    }
+}
+
+impl FromStr for ColorSpace {
+    type Err = Error;
+
+    fn from_str(string: &str) -> Result<ColorSpace, Self::Err> {
+        match string {
+            "srgb" => Ok(ColorSpace::SRGB),
+            "display-p3" => Ok(ColorSpace::DISPLAY_P3),
+            "rec2020" => Ok(ColorSpace::REC2020),
+            _ => Err(anyhow!("Invalid color space")),
+        }
+    }
+}
+
+/// Kinds of color vision deficiency that can be simulated.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Deficiency {
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+    Achromatopsia,
+}
+
+impl Deficiency {
+    /// Shows how `rgb` looks to a person with the deficiency. Dichromacies use
+    /// the simulation by Machado et al. at full severity.
+    // Source: https://www.inf.ufrgs.br/~oliveira/pubs_files/CVD_Simulation/CVD_Simulation.html
+    pub fn simulate(self, rgb: impl Into<ARGB16>) -> ARGB16 {
+        const PROTANOPIA: [[f32; 3]; 3] = [
+            [0.152_286, 1.052_583, -0.204_868],
+            [0.114_503, 0.786_281, 0.099_216],
+            [-0.003_882, -0.048_116, 1.051_998],
+        ];
+        const DEUTERANOPIA: [[f32; 3]; 3] = [
+            [0.367_322, 0.860_646, -0.227_968],
+            [0.280_085, 0.672_501, 0.047_413],
+            [-0.011_820, 0.042_940, 0.968_881],
+        ];
+        const TRITANOPIA: [[f32; 3]; 3] = [
+            [1.255_528, -0.076_749, -0.178_779],
+            [-0.078_411, 0.930_809, 0.147_602],
+            [0.004_733, 0.691_367, 0.303_900],
+        ];
+
+        let rgb = rgb.into();
+        let linear = linear_rgb(rgb);
+        let simulated = match self {
+            Deficiency::Protanopia => mul3(&PROTANOPIA, linear),
+            Deficiency::Deuteranopia => mul3(&DEUTERANOPIA, linear),
+            Deficiency::Tritanopia => mul3(&TRITANOPIA, linear),
+            // Without any cones, only the luminance is left
+            Deficiency::Achromatopsia => [mul3(&LINEAR_SRGB_TO_XYZ, linear)[1]; 3],
+        };
+        ARGB16::from_linear(rgb.a, simulated)
+    }
+}
+
+impl FromStr for Deficiency {
+    type Err = Error;
+
+    fn from_str(string: &str) -> Result<Deficiency, Self::Err> {
+        match string {
+            "protanopia" => Ok(Deficiency::Protanopia),
+            "deuteranopia" => Ok(Deficiency::Deuteranopia),
+            "tritanopia" => Ok(Deficiency::Tritanopia),
+            "achromatopsia" => Ok(Deficiency::Achromatopsia),
+            _ => Err(anyhow!("Invalid color vision deficiency")),
+        }
     }
 }
 
@@ -168,6 +1270,65 @@ fn test_compaction() {
     assert!(!ARGB::new(0xff, 0xff, 0xf7, 0xff).is_compactable());
 }
 
+#[test]
+fn test_compaction_with_alpha() {
+    assert!(ARGB::new(0xff, 0xff, 0xff, 0xff).is_compactable_with_alpha());
+    assert!(ARGB::new(0x88, 0x00, 0x11, 0x22).is_compactable_with_alpha());
+    assert!(!ARGB::new(0x80, 0x00, 0x11, 0x22).is_compactable_with_alpha());
+    assert!(!ARGB::new(0x88, 0x01, 0x11, 0x22).is_compactable_with_alpha());
+}
+
+#[test]
+fn test_argb16() {
+    assert_eq!(
+        ARGB16::from(ARGB::WHITE),
+        ARGB16::new(0xffff, 0xffff, 0xffff, 0xffff)
+    );
+    assert_eq!(
+        ARGB16::from(ARGB::new(0x80, 0x12, 0x34, 0x56)),
+        ARGB16::new(0x8080, 0x1212, 0x3434, 0x5656)
+    );
+    assert_eq!(
+        ARGB::from(ARGB16::new(0xffff, 0x8000, 0x807f, 0x0080)),
+        ARGB::new(0xff, 0x80, 0x80, 0x00)
+    );
+    for n in 0..=0xff {
+        let color = ARGB::new(n, n, n, n);
+        assert_eq!(ARGB::from(ARGB16::from(color)), color);
+    }
+}
+
+#[test]
+fn test_interpolate() {
+    let red = ARGB::new(0xff, 0xff, 0, 0);
+    let blue = ARGB::new(0x80, 0, 0, 0xff);
+    assert_eq!(red.interpolate(blue, 0.0), red);
+    assert_eq!(red.interpolate(blue, 1.0), ARGB { a: 0xff, ..blue });
+    assert_eq!(blue.interpolate(red, 0.5).a, 0x80);
+
+    // OKLab lightness is perceptually even, unlike gamma-encoded bytes
+    assert_eq!(
+        ARGB::BLACK.interpolate(ARGB::WHITE, 0.5),
+        ARGB::new(0xff, 0x63, 0x63, 0x63)
+    );
+
+    for n in 0..=255 {
+        let gray = ARGB::new(0xff, n, n, n);
+        assert_eq!(gray.lighten(0.0), gray);
+        assert_eq!(gray.darken(0.0), gray);
+        assert_eq!(gray.lighten(1.0), ARGB::WHITE);
+        assert_eq!(gray.darken(1.0), ARGB::BLACK);
+    }
+
+    let steps: Vec<f32> = (0..=10)
+        .map(|n| OkLab::from_rgb(red.lighten(n as f32 / 10.0)).l)
+        .collect();
+    let step = (1.0 - steps[0]) / 10.0;
+    for pair in steps.windows(2) {
+        assert_close(&[pair[1] - pair[0]], &[step], 2e-3);
+    }
+}
+
 #[test]
 fn test_hsl() {
     let rgb_white = ARGB::new(0xff, 0xff, 0xff, 0xff);
@@ -186,5 +1347,642 @@ fn test_hsl() {
     assert_eq!{HSL::from_rgb(rgb_yellow), HSL { h: 60.0, s: 100.0, l: 50.0 }};
 
     let rgb_cyan = ARGB::new(0xff, 14, 115, 123);
-    assert_eq!{HSL::from_rgb(rgb_cyan), HSL { h: 184.0, s: 80.0, l: 27.0 }};
+    let hsl = HSL::from_rgb(rgb_cyan);
+    assert_eq!(
+        (hsl.h.round(), hsl.s.round(), hsl.l.round()),
+        (184.0, 80.0, 27.0)
+    );
+    assert_close(&[hsl.l], &[137.0 / 510.0 * 100.0], 1e-4);
+
+    let hsl = HSL {
+        h: 210.0,
+        s: 100.0,
+        l: 56.0,
+    };
+    assert_eq!(
+        ARGB::from(ARGB16::from(hsl)),
+        ARGB::new(0xff, 0x1f, 0x8f, 0xff)
+    );
+}
+
+#[test]
+fn test_hsv() {
+    assert_eq!(HSV::from_rgb(ARGB::WHITE), HSV { h: 0.0, s: 0.0, v: 100.0 });
+    assert_eq!(HSV::from_rgb(ARGB::BLACK), HSV { h: 0.0, s: 0.0, v: 0.0 });
+
+    let rgb_red = ARGB::new(0xff, 0xff, 0, 0);
+    assert_eq!(HSV::from_rgb(rgb_red), HSV { h: 0.0, s: 100.0, v: 100.0 });
+
+    let rgb_green = ARGB::new(0xff, 0, 0x80, 0);
+    let hsv = HSV::from_rgb(rgb_green);
+    assert_eq!((hsv.h, hsv.s), (120.0, 100.0));
+    assert_eq!(hsv.v.round(), 50.0);
+
+    let rgb_magenta = ARGB::new(0xff, 0xff, 0, 0xff);
+    assert_eq!(HSV::from_rgb(rgb_magenta), HSV { h: 300.0, s: 100.0, v: 100.0 });
+
+    let rgb_cyan = ARGB::new(0xff, 14, 115, 123);
+    let hsv = HSV::from_rgb(rgb_cyan);
+    assert_eq!((hsv.h.round(), hsv.s.round(), hsv.v.round()), (184.0, 89.0, 48.0));
+}
+
+#[cfg(test)]
+fn assert_close(actual: &[f32], expected: &[f32], tolerance: f32) {
+    for (a, e) in actual.iter().zip(expected) {
+        assert!(
+            (a - e).abs() <= tolerance,
+            "{:?} is not within {} of {:?}",
+            actual,
+            tolerance,
+            expected
+        );
+    }
+}
+
+#[test]
+fn test_srgb_to_linear() {
+    assert_eq!(srgb_to_linear(0.0), 0.0);
+    assert_eq!(srgb_to_linear(1.0), 1.0);
+    assert_close(&[srgb_to_linear(0.04045)], &[0.003_130_8], 1e-6);
+    assert_close(&[srgb_to_linear(128.0 / 255.0)], &[0.215_861], 1e-6);
+}
+
+#[test]
+fn test_linear_to_srgb() {
+    assert_eq!(linear_to_srgb(0.0), 0.0);
+    assert_close(&[linear_to_srgb(1.0)], &[1.0], 1e-6);
+    for n in 0..=255u8 {
+        let c = f32::from(n) / 255.0;
+        assert_close(&[linear_to_srgb(srgb_to_linear(c))], &[c], 1e-5);
+    }
+}
+
+#[test]
+fn test_xyz() {
+    let xyz = |rgb| {
+        let XYZ { x, y, z } = XYZ::from_rgb(rgb);
+        [x, y, z]
+    };
+    assert_close(&xyz(ARGB::WHITE), &[0.950_456, 1.0, 1.089_058], 1e-5);
+    assert_close(
+        &xyz(ARGB::new(0xff, 0xff, 0, 0)),
+        &[0.412_391, 0.212_639, 0.019_331],
+        1e-5,
+    );
+    assert_close(
+        &xyz(ARGB::new(0xff, 0, 0xff, 0)),
+        &[0.357_584, 0.715_169, 0.119_195],
+        1e-5,
+    );
+    assert_close(
+        &xyz(ARGB::new(0xff, 0, 0, 0xff)),
+        &[0.180_481, 0.072_192, 0.950_532],
+        1e-5,
+    );
+}
+
+#[test]
+fn test_cct() {
+    let cct = |xyz| {
+        let CCT { kelvin, duv } = CCT::from_xyz(xyz);
+        [kelvin, duv]
+    };
+    let from_xy = |x: f32, y: f32| XYZ {
+        x: x / y,
+        y: 1.0,
+        z: (1.0 - x - y) / y,
+    };
+    assert_close(&cct(XYZ::D65)[..1], &[6504.0], 5.0);
+    assert_close(&cct(XYZ::D65)[1..], &[0.0032], 1e-4);
+    // CIE illuminant A is a black body at 2856 K
+    assert_close(&cct(from_xy(0.447_57, 0.407_45))[..1], &[2856.0], 5.0);
+    assert_close(&cct(from_xy(0.447_57, 0.407_45))[1..], &[0.0], 2e-4);
+    // CIE illuminant F2 (cool white fluorescent)
+    assert_close(&cct(from_xy(0.372_08, 0.375_29))[..1], &[4230.0], 10.0);
+    assert_close(&cct(from_xy(0.372_08, 0.375_29))[1..], &[0.0019], 2e-4);
+
+    let white = cct(XYZ::from_rgb(ARGB::WHITE));
+    assert_close(&cct(XYZ::from_rgb(ARGB::BLACK)), &white, 1e-3);
+    assert_close(
+        &cct(XYZ::from_rgb(ARGB::new(0xff, 0x80, 0x80, 0x80))),
+        &white,
+        1e-1,
+    );
+    assert!(CCT::from_rgb(ARGB::new(0xff, 0xff, 0xc0, 0x80)).kelvin < 4000.0);
+    assert!(CCT::from_rgb(ARGB::new(0xff, 0xc0, 0xd0, 0xff)).kelvin > 9000.0);
+}
+
+#[test]
+fn test_contrast() {
+    assert_eq!(relative_luminance(ARGB::BLACK), 0.0);
+    assert_close(&[relative_luminance(ARGB::WHITE)], &[1.0], 1e-6);
+    assert_close(
+        &[relative_luminance(ARGB::new(0xff, 0x80, 0x80, 0x80))],
+        &[0.215_861],
+        1e-6,
+    );
+
+    assert_close(&[contrast_ratio(ARGB::BLACK, ARGB::WHITE)], &[21.0], 1e-4);
+    assert_close(&[contrast_ratio(ARGB::WHITE, ARGB::BLACK)], &[21.0], 1e-4);
+    assert_close(&[contrast_ratio(ARGB::WHITE, ARGB::WHITE)], &[1.0], 1e-6);
+    // The lightest gray that passes AA for normal text on white
+    let gray = ARGB::new(0xff, 0x76, 0x76, 0x76);
+    assert_close(&[contrast_ratio(gray, ARGB::WHITE)], &[4.54], 1e-2);
+    assert_close(
+        &[contrast_ratio(
+            ARGB::new(0xff, 0x77, 0x77, 0x77),
+            ARGB::WHITE,
+        )],
+        &[4.48],
+        1e-2,
+    );
+}
+
+#[test]
+fn test_apca_contrast() {
+    let rgb = |rgb: u32| ARGB::new(0xff, (rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8);
+    // Source: https://github.com/Myndex/apca-w3 test vectors
+    let cases = [
+        (0x888888, 0xffffff, 63.056_47),
+        (0xffffff, 0x888888, -68.541_46),
+        (0x000000, 0xaaaaaa, 58.146_263),
+        (0xaaaaaa, 0x000000, -56.241_13),
+        (0x112233, 0xddeeff, 91.668_31),
+        (0xddeeff, 0x112233, -93.067_7),
+    ];
+    for &(text, background, expected) in cases.iter() {
+        assert_close(
+            &[apca_contrast(rgb(text), rgb(background))],
+            &[expected],
+            1e-3,
+        );
+    }
+
+    assert_eq!(apca_contrast(ARGB::WHITE, ARGB::WHITE), 0.0);
+    let gray = ARGB::new(0xff, 0x44, 0x44, 0x44);
+    assert_eq!(apca_contrast(ARGB::new(0xff, 0x33, 0x33, 0x33), gray), 0.0);
+}
+
+#[test]
+fn test_is_dark() {
+    assert!(ARGB::BLACK.is_dark());
+    assert!(!ARGB::WHITE.is_dark());
+    assert!(ARGB::new(0xff, 0, 0, 0xff).is_dark());
+    assert!(!ARGB::new(0xff, 0, 0xff, 0).is_dark());
+    assert!(ARGB::new(0xff, 0x74, 0x74, 0x74).is_dark());
+    assert!(!ARGB::new(0xff, 0x76, 0x76, 0x76).is_dark());
+}
+
+#[test]
+fn test_lab() {
+    let lab = |rgb| {
+        let Lab { l, a, b } = Lab::from_rgb(rgb);
+        [l, a, b]
+    };
+    assert_close(&lab(ARGB::BLACK), &[0.0, 0.0, 0.0], 1e-4);
+    assert_close(&lab(ARGB::WHITE), &[100.0, 0.0, 0.0], 1e-3);
+    assert_close(
+        &lab(ARGB::new(0xff, 0x80, 0x80, 0x80)),
+        &[53.585, 0.0, 0.0],
+        1e-3,
+    );
+    assert_close(
+        &lab(ARGB::new(0xff, 0xff, 0, 0)),
+        &[53.2371, 80.0901, 67.2033],
+        1e-2,
+    );
+    assert_close(
+        &lab(ARGB::new(0xff, 0, 0xff, 0)),
+        &[87.7355, -86.1816, 83.1866],
+        1e-2,
+    );
+    assert_close(
+        &lab(ARGB::new(0xff, 0, 0, 0xff)),
+        &[32.3009, 79.1953, -107.8555],
+        1e-2,
+    );
+    assert_close(
+        &lab(ARGB::new(0xff, 14, 115, 123)),
+        &[43.9219, -23.1378, -12.0097],
+        1e-2,
+    );
+}
+
+#[test]
+fn test_delta_e() {
+    let lab = |l, a, b| Lab { l, a, b };
+    // Source: http://www2.ece.rochester.edu/~gsharma/ciede2000/
+    let sharma = [
+        ((50.0, 2.6772, -79.7751), (50.0, 0.0, -82.7485), 2.0425),
+        ((50.0, 3.1571, -77.2803), (50.0, 0.0, -82.7485), 2.8615),
+        ((50.0, 2.8361, -74.0200), (50.0, 0.0, -82.7485), 3.4412),
+        ((50.0, -1.3802, -84.2814), (50.0, 0.0, -82.7485), 1.0000),
+        ((50.0, -1.1848, -84.8006), (50.0, 0.0, -82.7485), 1.0000),
+        ((50.0, -0.9009, -85.5211), (50.0, 0.0, -82.7485), 1.0000),
+        ((50.0, 0.0, 0.0), (50.0, -1.0, 2.0), 2.3669),
+        ((50.0, -1.0, 2.0), (50.0, 0.0, 0.0), 2.3669),
+        ((50.0, 2.4900, -0.0010), (50.0, -2.4900, 0.0009), 7.1792),
+        ((50.0, 2.4900, -0.0010), (50.0, -2.4900, 0.0010), 7.1792),
+        ((50.0, 2.4900, -0.0010), (50.0, -2.4900, 0.0011), 7.2195),
+        ((50.0, 2.4900, -0.0010), (50.0, -2.4900, 0.0012), 7.2195),
+        ((50.0, -0.0010, 2.4900), (50.0, 0.0009, -2.4900), 4.8045),
+        ((50.0, -0.0010, 2.4900), (50.0, 0.0010, -2.4900), 4.8045),
+        ((50.0, -0.0010, 2.4900), (50.0, 0.0011, -2.4900), 4.7461),
+        ((50.0, 2.5000, 0.0), (50.0, 0.0, -2.5000), 4.3065),
+        ((50.0, 2.5000, 0.0), (73.0, 25.0, -18.0), 27.1492),
+        ((50.0, 2.5000, 0.0), (61.0, -5.0, 29.0), 22.8977),
+        ((50.0, 2.5000, 0.0), (56.0, -27.0, -3.0), 31.9030),
+        ((50.0, 2.5000, 0.0), (58.0, 24.0, 15.0), 19.4535),
+        ((50.0, 2.5000, 0.0), (50.0, 3.1736, 0.5854), 1.0000),
+        ((50.0, 2.5000, 0.0), (50.0, 3.2972, 0.0), 1.0000),
+        ((50.0, 2.5000, 0.0), (50.0, 1.8634, 0.5757), 1.0000),
+        ((50.0, 2.5000, 0.0), (50.0, 3.2592, 0.3350), 1.0000),
+        (
+            (60.2574, -34.0099, 36.2677),
+            (60.4626, -34.1751, 39.4387),
+            1.2644,
+        ),
+        (
+            (63.0109, -31.0961, -5.8663),
+            (62.8187, -29.7946, -4.0864),
+            1.2630,
+        ),
+        (
+            (61.2901, 3.7196, -5.3901),
+            (61.4292, 2.2480, -4.9620),
+            1.8731,
+        ),
+        (
+            (35.0831, -44.1164, 3.7933),
+            (35.0232, -40.0716, 1.5901),
+            1.8645,
+        ),
+        (
+            (22.7233, 20.0904, -46.6940),
+            (23.0331, 14.9730, -42.5619),
+            2.0373,
+        ),
+        (
+            (36.4612, 47.8580, 18.3852),
+            (36.2715, 50.5065, 21.2231),
+            1.4146,
+        ),
+        (
+            (90.8027, -2.0831, 1.4410),
+            (91.1528, -1.6435, 0.0447),
+            1.4441,
+        ),
+        (
+            (90.9257, -0.5406, -0.9208),
+            (88.6381, -0.8985, -0.7239),
+            1.5381,
+        ),
+        (
+            (6.7747, -0.2908, -2.4247),
+            (5.8714, -0.0985, -2.2286),
+            0.6377,
+        ),
+        (
+            (2.0776, 0.0795, -1.1350),
+            (0.9033, -0.0636, -0.5514),
+            0.9082,
+        ),
+    ];
+    for &((l1, a1, b1), (l2, a2, b2), expected) in sharma.iter() {
+        let (first, second) = (lab(l1, a1, b1), lab(l2, a2, b2));
+        assert_close(&[first.delta_e2000(&second)], &[expected], 1e-4);
+        assert_close(&[second.delta_e2000(&first)], &[expected], 1e-4);
+    }
+
+    let (first, second) = (lab(50.0, 2.6772, -79.7751), lab(50.0, 0.0, -82.7485));
+    assert_close(&[first.delta_e76(&second)], &[4.001_063], 1e-4);
+    assert_close(&[first.delta_e94(&second)], &[1.395_039], 1e-4);
+    assert_eq!(first.delta_e76(&first), 0.0);
+    assert_eq!(first.delta_e94(&first), 0.0);
+    assert_eq!(first.delta_e2000(&first), 0.0);
+}
+
+#[test]
+fn test_lch() {
+    let lch = |rgb| {
+        let LCh { l, c, h } = LCh::from_rgb(rgb);
+        [l, c, h]
+    };
+    assert_close(&lch(ARGB::WHITE), &[100.0, 0.0, 0.0], 1e-3);
+    assert_close(
+        &lch(ARGB::new(0xff, 0xff, 0, 0)),
+        &[53.2371, 104.55, 39.9999],
+        1e-2,
+    );
+    assert_close(
+        &lch(ARGB::new(0xff, 0, 0, 0xff)),
+        &[32.3009, 133.8084, 306.2888],
+        1e-2,
+    );
+    assert_close(
+        &lch(ARGB::new(0xff, 14, 115, 123)),
+        &[43.9219, 26.069, 207.4316],
+        1e-2,
+    );
+}
+
+#[test]
+fn test_oklab() {
+    let oklab = |rgb| {
+        let OkLab { l, a, b } = OkLab::from_rgb(rgb);
+        [l, a, b]
+    };
+    assert_close(&oklab(ARGB::BLACK), &[0.0, 0.0, 0.0], 1e-5);
+    assert_close(&oklab(ARGB::WHITE), &[1.0, 0.0, 0.0], 1e-5);
+    assert_close(
+        &oklab(ARGB::new(0xff, 0xff, 0, 0)),
+        &[0.62796, 0.22486, 0.12585],
+        1e-4,
+    );
+    assert_close(
+        &oklab(ARGB::new(0xff, 0, 0xff, 0)),
+        &[0.86644, -0.23389, 0.1795],
+        1e-4,
+    );
+    assert_close(
+        &oklab(ARGB::new(0xff, 0, 0, 0xff)),
+        &[0.45201, -0.03246, -0.31153],
+        1e-4,
+    );
+}
+
+#[test]
+fn test_oklab_to_rgb() {
+    for &rgb in [
+        0x000000, 0xffffff, 0xff0000, 0x00ff00, 0x0000ff, 0x1e90fe, 0x0e737b,
+    ]
+    .iter()
+    {
+        let color = ARGB::new(0xff, (rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8);
+        assert_eq!(ARGB::from(OkLab::from_rgb(color).to_rgb()), color);
+        assert_eq!(
+            ARGB::from(OkLCh::from_rgb(color).to_oklab().to_rgb()),
+            color
+        );
+        assert_eq!(ARGB::from(OkLCh::from_rgb(color).to_rgb()), color);
+    }
+
+    // Out of gamut colors keep their lightness and hue
+    let out_of_gamut = OkLCh {
+        l: 0.7,
+        c: 0.4,
+        h: 150.0,
+    };
+    let mapped = OkLCh::from_rgb(out_of_gamut.to_rgb());
+    assert_close(&[mapped.l, mapped.h], &[0.7, 150.0], 2e-2);
+    assert!(mapped.c < 0.4);
+}
+
+#[test]
+fn test_oklch() {
+    let oklch = |rgb| {
+        let OkLCh { l, c, h } = OkLCh::from_rgb(rgb);
+        [l, c, h]
+    };
+    assert_close(&oklch(ARGB::WHITE), &[1.0, 0.0, 0.0], 1e-5);
+    assert_close(
+        &oklch(ARGB::new(0xff, 0xff, 0, 0)),
+        &[0.62796, 0.25768, 29.234],
+        1e-3,
+    );
+    assert_close(
+        &oklch(ARGB::new(0xff, 14, 115, 123)),
+        &[0.50769, 0.0834, 203.633],
+        1e-3,
+    );
+}
+
+#[test]
+fn test_cmyk() {
+    let cmyk = |rgb, separation| {
+        let CMYK { c, m, y, k } = CMYK::from_rgb(rgb, separation);
+        [c, m, y, k]
+    };
+    let naive = InkSeparation::NAIVE;
+    assert_close(&cmyk(ARGB::BLACK, naive), &[0.0, 0.0, 0.0, 100.0], 1e-4);
+    assert_close(&cmyk(ARGB::WHITE, naive), &[0.0, 0.0, 0.0, 0.0], 1e-4);
+    assert_close(
+        &cmyk(ARGB::new(0xff, 0xff, 0, 0), naive),
+        &[0.0, 100.0, 100.0, 0.0],
+        1e-4,
+    );
+    assert_close(
+        &cmyk(ARGB::new(0xff, 0x33, 0x66, 0x99), naive),
+        &[66.6667, 33.3333, 0.0, 40.0],
+        1e-3,
+    );
+
+    let no_black = InkSeparation {
+        black_generation: 0.0,
+        under_color_removal: 1.0,
+    };
+    assert_close(
+        &cmyk(ARGB::new(0xff, 0x33, 0x66, 0x99), no_black),
+        &[80.0, 60.0, 40.0, 0.0],
+        1e-3,
+    );
+
+    let half = InkSeparation {
+        black_generation: 0.5,
+        under_color_removal: 1.0,
+    };
+    assert_close(&cmyk(ARGB::BLACK, half), &[50.0, 50.0, 50.0, 50.0], 1e-4);
+    assert_close(
+        &cmyk(ARGB::new(0xff, 0x33, 0x66, 0x99), half),
+        &[60.0, 40.0, 20.0, 20.0],
+        1e-3,
+    );
+
+    let no_removal = InkSeparation {
+        black_generation: 1.0,
+        under_color_removal: 0.0,
+    };
+    assert_close(
+        &cmyk(ARGB::new(0xff, 0x33, 0x66, 0x99), no_removal),
+        &[80.0, 60.0, 40.0, 40.0],
+        1e-3,
+    );
+}
+
+#[test]
+fn test_hwb() {
+    let hwb = |rgb| {
+        let HWB { h, w, b } = HWB::from_rgb(rgb);
+        [h, w, b]
+    };
+    assert_close(&hwb(ARGB::WHITE), &[0.0, 100.0, 0.0], 1e-4);
+    assert_close(&hwb(ARGB::BLACK), &[0.0, 0.0, 100.0], 1e-4);
+    assert_close(&hwb(ARGB::new(0xff, 0xff, 0, 0)), &[0.0, 0.0, 0.0], 1e-4);
+    assert_close(
+        &hwb(ARGB::new(0xff, 0x80, 0xff, 0x80)),
+        &[120.0, 50.1961, 0.0],
+        1e-3,
+    );
+    assert_close(
+        &hwb(ARGB::new(0xff, 14, 115, 123)),
+        &[184.4037, 5.4902, 51.7647],
+        1e-3,
+    );
+
+    // Whiteness and blackness beyond 100 in total give a gray
+    let gray = HWB {
+        h: 90.0,
+        w: 30.0,
+        b: 90.0,
+    };
+    assert_eq!(
+        ARGB::from(ARGB16::from(gray)),
+        ARGB::new(0xff, 0x40, 0x40, 0x40)
+    );
+}
+
+#[test]
+fn test_deficiency() {
+    let simulate = |deficiency: Deficiency, color| ARGB::from(deficiency.simulate(color));
+    let red = ARGB::new(0xff, 0xff, 0, 0);
+    let deficiencies = [
+        Deficiency::Protanopia,
+        Deficiency::Deuteranopia,
+        Deficiency::Tritanopia,
+        Deficiency::Achromatopsia,
+    ];
+
+    for &deficiency in deficiencies.iter() {
+        assert_eq!(simulate(deficiency, ARGB::WHITE), ARGB::WHITE);
+        assert_eq!(simulate(deficiency, ARGB::BLACK), ARGB::BLACK);
+        assert_eq!(simulate(deficiency, ARGB::TRANSPARENT), ARGB::TRANSPARENT);
+    }
+
+    assert_eq!(
+        simulate(Deficiency::Protanopia, red),
+        ARGB::new(0xff, 0x6d, 0x5f, 0x00)
+    );
+    assert_eq!(
+        simulate(Deficiency::Achromatopsia, red),
+        ARGB::new(0xff, 0x7f, 0x7f, 0x7f)
+    );
+
+    assert_eq!(
+        "tritanopia".parse::<Deficiency>().unwrap(),
+        Deficiency::Tritanopia
+    );
+    assert!("protan".parse::<Deficiency>().is_err());
+}
+
+#[test]
+fn test_color_space() {
+    let red = ARGB16::new(0xffff, 0xffff, 0, 0);
+    let green = ARGB16::new(0xffff, 0, 0xffff, 0);
+    let white = ARGB16::new(0xffff, 0xffff, 0xffff, 0xffff);
+    let assert_all_close = |actual: [f32; 3], expected: [f32; 3]| {
+        assert_close(&actual, &expected, 1e-4);
+    };
+
+    let xyz = ColorSpace::SRGB.to_xyz(red);
+    assert_all_close(ColorSpace::DISPLAY_P3.encode(xyz), [0.9175, 0.2003, 0.1386]);
+    assert_all_close(ColorSpace::REC2020.encode(xyz), [0.7920, 0.2310, 0.0738]);
+    assert_all_close(
+        ColorSpace::REC2020.encode(ColorSpace::DISPLAY_P3.to_xyz(green)),
+        [0.4318, 0.9707, 0.0792],
+    );
+    assert_eq!(ColorSpace::SRGB.to_srgb(red), red);
+    assert_eq!(ColorSpace::REC2020.to_srgb(white), white);
+
+    // Wider colors keep their lightness and hue when they are mapped
+    let p3_red = OkLCh::from_oklab(OkLab::from_xyz(ColorSpace::DISPLAY_P3.to_xyz(red)));
+    let mapped = ColorSpace::DISPLAY_P3.to_srgb(red);
+    let mapped_lch = OkLCh::from_rgb(mapped);
+    assert_close(&[mapped_lch.l], &[p3_red.l], 1e-3);
+    assert_close(&[mapped_lch.h], &[p3_red.h], 0.1);
+    assert!(mapped_lch.c < p3_red.c);
+    assert_eq!(mapped.r, 0xffff);
+
+    let mapped = ColorSpace::DISPLAY_P3.encode(ColorSpace::REC2020.to_xyz(green));
+    assert!(mapped.iter().all(|c| (0.0..=1.0).contains(c)));
+
+    let translucent = ARGB16::new(0x8000, 0x4000, 0x8000, 0x2000);
+    assert_eq!(ColorSpace::REC2020.to_srgb(translucent).a, 0x8000);
+
+    assert_eq!(
+        "display-p3".parse::<ColorSpace>().unwrap(),
+        ColorSpace::DISPLAY_P3
+    );
+    assert!("adobe-rgb".parse::<ColorSpace>().is_err());
+}
+
+#[test]
+fn test_transfer() {
+    let transfers = [
+        Transfer::Srgb,
+        Transfer::Rec2020,
+        Transfer::Parametric([2.2, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+        Transfer::Parametric([
+            2.4,
+            1.0 / 1.055,
+            0.055 / 1.055,
+            1.0 / 12.92,
+            0.04045,
+            0.0,
+            0.0,
+        ]),
+        Transfer::Table(vec![0.0, 0.1, 0.3, 0.6, 1.0]),
+    ];
+    for transfer in transfers.iter() {
+        for n in 0..=20 {
+            let c = n as f32 / 20.0;
+            let decoded = transfer.decode(c);
+            assert!((0.0..=1.0).contains(&decoded), "{:?}", transfer);
+            assert_close(&[transfer.encode(decoded)], &[c], 1e-4);
+        }
+    }
+    assert_close(
+        &[Transfer::Table(vec![0.0, 0.5, 1.0]).decode(0.25)],
+        &[0.25],
+        1e-6,
+    );
+}
+
+// 8-bit colors are converted to the model and back on a grid that includes the
+// extremes of each channel. Deep colors are checked on a pseudorandom sample.
+#[cfg(test)]
+fn assert_round_trip<T>(from_rgb: impl Fn(ARGB16) -> T)
+where
+    ARGB16: From<T>,
+{
+    let levels = || (0..=0xffu8).step_by(3);
+    for r in levels() {
+        for g in levels() {
+            for b in levels() {
+                let color = ARGB::new(0xff, r, g, b);
+                assert_eq!(ARGB::from(ARGB16::from(from_rgb(color.into()))), color);
+            }
+        }
+    }
+
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    for _ in 0..10_000 {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let color = ARGB16::new(
+            0xffff,
+            state as u16,
+            (state >> 16) as u16,
+            (state >> 32) as u16,
+        );
+        assert_eq!(ARGB16::from(from_rgb(color)), color);
+    }
+}
+
+#[test]
+fn test_round_trip() {
+    assert_round_trip(HSL::from_rgb);
+    assert_round_trip(HSV::from_rgb);
+    assert_round_trip(HWB::from_rgb);
+    assert_round_trip(Lab::from_rgb);
 }
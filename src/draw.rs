@@ -6,6 +6,15 @@ fn is_inside_circle(x: isize, y: isize, r: isize) -> bool {
     (x - r).pow(2) + (y - r).pow(2) < r.pow(2)
 }
 
+// Mixing in OKLab is slow, so grid colors are only computed once per screenshot pixel
+fn grid_color(color: ARGB) -> u32 {
+    if color.is_dark() {
+        color.lighten(0.2).into()
+    } else {
+        color.darken(0.2).into()
+    }
+}
+
 #[inline]
 fn border_color(color: ARGB) -> u32 {
     if color.is_dark() {
@@ -20,9 +29,9 @@ pub fn draw_magnifying_glass(
     screenshot: &PixelSquare<&[ARGB]>,
     pixel_size: usize,
 ) {
-    assert!(pixel_size % 2 != 0, "pixel_size must be odd");
-    assert!(cursor.width() % 2 != 0, "cursor.width must be odd");
-    assert!(screenshot.width() % 2 != 0, "screenshot.width must be odd");
+    assert!(!pixel_size.is_multiple_of(2), "pixel_size must be odd");
+    assert!(!cursor.width().is_multiple_of(2), "cursor.width must be odd");
+    assert!(!screenshot.width().is_multiple_of(2), "screenshot.width must be odd");
 
     let transparent: u32 = ARGB::TRANSPARENT.into();
 
@@ -39,6 +48,11 @@ pub fn draw_magnifying_glass(
     let screenshot_center = screenshot_width / 2;
     let offset = screenshot_center * pixel_size - cursor_center_pixel;
 
+    let grid_colors: Vec<u32> = (0..screenshot.width() * screenshot.width())
+        .map(|idx| grid_color(screenshot[idx]))
+        .collect();
+    let grid_colors = PixelSquare::new(&grid_colors[..], screenshot.width());
+
     for cx in 0..cursor_width {
         for cy in 0..cursor_width {
             // screenshot coordinates
@@ -61,12 +75,7 @@ pub fn draw_magnifying_glass(
                     if is_center_x && is_center_y {
                         border_color(screenshot_color)
                     } else {
-                        // grid color
-                        if screenshot_color.is_dark() {
-                            screenshot_color.lighten(0.2).into()
-                        } else {
-                            screenshot_color.darken(0.2).into()
-                        }
+                        grid_colors[(sx, sy)]
                     }
                 } else {
                     screenshot_color.into()
@@ -1,29 +1,94 @@
+use std::iter;
 use std::num::ParseIntError;
 use std::str::FromStr;
-use std::{fmt, iter};
 
 use nom::branch::alt;
 use nom::bytes::complete::{tag, take_till1};
 use nom::character::complete::{anychar, digit1};
-use nom::combinator::{all_consuming, complete, map, map_res, opt, value};
+use nom::combinator::{
+    all_consuming, complete, flat_map, map, map_res, opt, success, value, verify,
+};
 use nom::error::{FromExternalError, ParseError};
 use nom::multi::many0;
-use nom::sequence::{preceded, terminated, tuple};
+use nom::sequence::{delimited, pair, preceded, terminated, tuple};
 use nom::IResult;
 
 use anyhow::{anyhow, Error, Result};
 
-use crate::color::{ ARGB, HSL };
+use crate::ansi;
+use crate::color::{
+    apca_contrast, contrast_ratio, ColorSpace, Deficiency, InkSeparation, LCh, Lab, OkLCh, OkLab,
+    ARGB, ARGB16, CCT, CMYK, HSL, HSV, HWB, XYZ,
+};
+use crate::names;
 
-pub struct FormatString(Vec<FormatPart>);
+pub struct FormatString {
+    parts: Vec<FormatPart>,
+    separation: InkSeparation,
+}
 
 #[derive(Clone, Copy)]
 enum Channel {
     R,
     G,
     B,
+    A,
+    HslH,
+    HslS,
+    HslL,
+    HsvH,
+    HsvS,
+    HsvV,
+    LabL,
+    LabA,
+    LabB,
+    LchL,
+    LchC,
+    LchH,
+    XyzX,
+    XyzY,
+    XyzZ,
+    OkLabL,
+    OkLabA,
+    OkLabB,
+    OkLchL,
+    OkLchC,
+    OkLchH,
+    CmykC,
+    CmykM,
+    CmykY,
+    CmykK,
+    HwbH,
+    HwbW,
+    HwbB,
+    CctK,
+    CctDuv,
+    P3R,
+    P3G,
+    P3B,
+    Rec2020R,
+    Rec2020G,
+    Rec2020B,
+    Ansi16,
+    Ansi256,
+    Sgr16,
+    SimulatedR(Deficiency),
+    SimulatedG(Deficiency),
+    SimulatedB(Deficiency),
+    Wcag(Background),
+    Apca(Background),
+    X,
+    Y,
+    Screen,
 }
 
+// Deepest bit depth that channels can be scaled to
+const MAX_BITS: u8 = 24;
+
+// Most decimals that can be asked for, more is beyond the precision of f32
+const MAX_PRECISION: usize = 9;
+
+#[derive(Clone, Copy)]
 struct Pad {
     char: char,
     len: u16,
@@ -36,6 +101,16 @@ enum NumberFormat {
     Decimal,
     Octal,
     Binary,
+    /// The value divided by the scale of the channel
+    Float,
+    /// The value as a percentage of the scale of the channel
+    Percentage,
+}
+
+#[derive(Clone, Copy)]
+enum Background {
+    White,
+    Black,
 }
 
 enum FormatPart {
@@ -43,11 +118,17 @@ enum FormatPart {
     Expansion {
         channel: Channel,
         format: NumberFormat,
+        precision: Option<usize>,
+        bits: Option<u8>,
         pad: Option<Pad>,
     },
+    Name(Option<Pad>),
+    TextColor(Option<Pad>),
+    Color(Format),
+    Time,
 }
 
-fn literal<'a, E>(input: &'a str) -> IResult<&str, FormatPart, E>
+fn literal<'a, E>(input: &'a str) -> IResult<&'a str, FormatPart, E>
 where
     E: ParseError<&'a str>,
 {
@@ -56,18 +137,121 @@ where
     })(input)
 }
 
-fn channel<'a, E>(input: &'a str) -> IResult<&str, Channel, E>
+fn channel<'a, E>(input: &'a str) -> IResult<&'a str, Channel, E>
 where
     E: ParseError<&'a str>,
 {
+    let deficiency = alt((
+        value(Deficiency::Protanopia, tag("protanopia")),
+        value(Deficiency::Deuteranopia, tag("deuteranopia")),
+        value(Deficiency::Tritanopia, tag("tritanopia")),
+        value(Deficiency::Achromatopsia, tag("achromatopsia")),
+    ));
+    let simulated = map(
+        pair(deficiency, alt((tag(".r"), tag(".g"), tag(".b")))),
+        |(deficiency, component)| match component {
+            ".r" => Channel::SimulatedR(deficiency),
+            ".g" => Channel::SimulatedG(deficiency),
+            _ => Channel::SimulatedB(deficiency),
+        },
+    );
+
+    let background = || {
+        alt((
+            value(Background::White, tag("white")),
+            value(Background::Black, tag("black")),
+        ))
+    };
+    let contrast = alt((
+        map(preceded(tag("wcag."), background()), Channel::Wcag),
+        map(preceded(tag("apca."), background()), Channel::Apca),
+    ));
+
+    // Single letter channels come last since they are prefixes of the names of
+    // other channels (e.g. "ansi16")
     alt((
-        value(Channel::R, tag("r")),
-        value(Channel::G, tag("g")),
-        value(Channel::B, tag("b")),
+        alt((
+            value(Channel::HslH, tag("hsl.h")),
+            value(Channel::HslS, tag("hsl.s")),
+            value(Channel::HslL, tag("hsl.l")),
+        )),
+        alt((
+            value(Channel::HsvH, tag("hsv.h")),
+            value(Channel::HsvS, tag("hsv.s")),
+            value(Channel::HsvV, tag("hsv.v")),
+        )),
+        alt((
+            value(Channel::LabL, tag("lab.l")),
+            value(Channel::LabA, tag("lab.a")),
+            value(Channel::LabB, tag("lab.b")),
+        )),
+        alt((
+            value(Channel::LchL, tag("lch.l")),
+            value(Channel::LchC, tag("lch.c")),
+            value(Channel::LchH, tag("lch.h")),
+        )),
+        alt((
+            value(Channel::XyzX, tag("xyz.x")),
+            value(Channel::XyzY, tag("xyz.y")),
+            value(Channel::XyzZ, tag("xyz.z")),
+        )),
+        alt((
+            value(Channel::OkLabL, tag("oklab.l")),
+            value(Channel::OkLabA, tag("oklab.a")),
+            value(Channel::OkLabB, tag("oklab.b")),
+        )),
+        alt((
+            value(Channel::OkLchL, tag("oklch.l")),
+            value(Channel::OkLchC, tag("oklch.c")),
+            value(Channel::OkLchH, tag("oklch.h")),
+        )),
+        alt((
+            value(Channel::CmykC, tag("cmyk.c")),
+            value(Channel::CmykM, tag("cmyk.m")),
+            value(Channel::CmykY, tag("cmyk.y")),
+            value(Channel::CmykK, tag("cmyk.k")),
+        )),
+        alt((
+            value(Channel::HwbH, tag("hwb.h")),
+            value(Channel::HwbW, tag("hwb.w")),
+            value(Channel::HwbB, tag("hwb.b")),
+        )),
+        alt((
+            value(Channel::CctK, tag("cct.k")),
+            value(Channel::CctDuv, tag("cct.duv")),
+        )),
+        alt((
+            value(Channel::P3R, tag("p3.r")),
+            value(Channel::P3G, tag("p3.g")),
+            value(Channel::P3B, tag("p3.b")),
+        )),
+        alt((
+            value(Channel::Rec2020R, tag("rec2020.r")),
+            value(Channel::Rec2020G, tag("rec2020.g")),
+            value(Channel::Rec2020B, tag("rec2020.b")),
+        )),
+        alt((
+            value(Channel::Ansi16, tag("ansi16")),
+            value(Channel::Ansi256, tag("ansi256")),
+            value(Channel::Sgr16, tag("sgr16")),
+        )),
+        simulated,
+        contrast,
+        alt((
+            value(Channel::X, tag("x")),
+            value(Channel::Y, tag("y")),
+            value(Channel::Screen, tag("screen")),
+        )),
+        alt((
+            value(Channel::R, tag("r")),
+            value(Channel::G, tag("g")),
+            value(Channel::B, tag("b")),
+            value(Channel::A, tag("a")),
+        )),
     ))(input)
 }
 
-fn format<'a, E>(input: &'a str) -> IResult<&str, NumberFormat, E>
+fn format<'a, E>(input: &'a str) -> IResult<&'a str, NumberFormat, E>
 where
     E: ParseError<&'a str>,
 {
@@ -77,10 +261,34 @@ where
         value(NumberFormat::Octal, tag("o")),
         value(NumberFormat::Binary, tag("B")),
         value(NumberFormat::Decimal, tag("d")),
+        value(NumberFormat::Float, tag("f")),
+        value(NumberFormat::Percentage, tag("%")),
     ))(input)
 }
 
-fn pad<'a, E>(input: &'a str) -> IResult<&str, Pad, E>
+fn bits<'a, E>(input: &'a str) -> IResult<&'a str, u8, E>
+where
+    E: ParseError<&'a str> + FromExternalError<&'a str, ParseIntError>,
+{
+    let depth = map_res(digit1, |s: &str| s.parse::<u8>());
+    preceded(
+        tag(":"),
+        verify(depth, |bits| (1..=MAX_BITS).contains(bits)),
+    )(input)
+}
+
+fn precision<'a, E>(input: &'a str) -> IResult<&'a str, usize, E>
+where
+    E: ParseError<&'a str> + FromExternalError<&'a str, ParseIntError>,
+{
+    let decimals = map_res(digit1, |s: &str| s.parse::<usize>());
+    preceded(
+        tag("."),
+        verify(decimals, |decimals| *decimals <= MAX_PRECISION),
+    )(input)
+}
+
+fn pad<'a, E>(input: &'a str) -> IResult<&'a str, Pad, E>
 where
     E: ParseError<&'a str> + FromExternalError<&'a str, ParseIntError>,
 {
@@ -88,31 +296,106 @@ where
     map(tuple((anychar, digit)), |(char, len)| Pad { char, len })(input)
 }
 
-fn expansion<'a, E>(input: &'a str) -> IResult<&str, FormatPart, E>
+fn expansion<'a, E>(input: &'a str) -> IResult<&'a str, FormatPart, E>
 where
     E: ParseError<&'a str> + FromExternalError<&'a str, ParseIntError>,
 {
     let escape = map(tag("%%"), |_| FormatPart::Literal("%".to_owned()));
+    // Expansions that print words only take padding
+    let padded = |word| {
+        delimited(
+            tag("%{"),
+            terminated(opt(verify(pad, |pad| pad.char != '.')), tag(word)),
+            tag("}"),
+        )
+    };
+    let name = map(padded("name"), FormatPart::Name);
+    let time = map(tag("%{time}"), |_| FormatPart::Time);
+    let hex = alt((
+        value(Format::LowercaseHex(HexCompaction::Full), tag("%{hex}")),
+        value(Format::UppercaseHex(HexCompaction::Full), tag("%{HEX}")),
+        value(Format::LowercaseHex(HexCompaction::Compact), tag("%{hex!}")),
+        value(Format::UppercaseHex(HexCompaction::Compact), tag("%{HEX!}")),
+        value(
+            Format::LowercaseHexAlpha(HexCompaction::Full),
+            tag("%{hexa}"),
+        ),
+        value(
+            Format::UppercaseHexAlpha(HexCompaction::Full),
+            tag("%{HEXA}"),
+        ),
+        value(
+            Format::LowercaseHexAlpha(HexCompaction::Compact),
+            tag("%{hexa!}"),
+        ),
+        value(
+            Format::UppercaseHexAlpha(HexCompaction::Compact),
+            tag("%{HEXA!}"),
+        ),
+        value(Format::LowercaseAlphaHex, tag("%{ahex}")),
+        value(Format::UppercaseAlphaHex, tag("%{AHEX}")),
+    ));
+    let text_color = map(padded("wcag.text"), FormatPart::TextColor);
+    // Named channels may start with a letter that is also a number format
+    // specifier (e.g. "hsv.h") or look like padding (e.g. "p3.r"), and padding
+    // characters may be channels themselves (e.g. "b8r"). Each way of reading
+    // the specifiers is tried up to the closing brace so that a wrong guess
+    // falls through to the next one. A leading dot starts the precision rather
+    // than padding with dots.
+    let specifiers = |pad: Option<Pad>| {
+        let format_and_channel = alt((
+            tuple((map(format, Some), channel, opt(bits), tag("}"))),
+            tuple((success(None), channel, opt(bits), tag("}"))),
+        ));
+        verify(
+            map(
+                pair(opt(precision), format_and_channel),
+                move |(precision, (format, channel, bits, _))| {
+                    (pad, precision, format, channel, bits)
+                },
+            ),
+            |(_, precision, format, channel, bits)| {
+                (precision.is_none() || format.is_none_or(NumberFormat::has_precision))
+                    && (bits.is_none() || channel.is_bounded())
+            },
+        )
+    };
     let inner = complete(map(
-        tuple((opt(pad), opt(format), channel)),
-        |(pad, format, channel)| FormatPart::Expansion {
+        alt((
+            specifiers(None),
+            flat_map(verify(pad, |pad| pad.char != '.'), move |pad| {
+                specifiers(Some(pad))
+            }),
+        )),
+        |(pad, precision, format, channel, bits)| FormatPart::Expansion {
             channel,
             pad,
+            precision,
+            bits,
             format: format.unwrap_or(NumberFormat::Decimal),
         },
     ));
-    let expansion = preceded(tag("%{"), terminated(inner, tag("}")));
-    alt((escape, expansion))(input)
+    let expansion = preceded(tag("%{"), inner);
+    alt((
+        escape,
+        name,
+        time,
+        map(hex, FormatPart::Color),
+        text_color,
+        expansion,
+    ))(input)
 }
 
-fn parse_format_string<'a, E>(input: &'a str) -> IResult<&str, FormatString, E>
+fn parse_format_string<'a, E>(input: &'a str) -> IResult<&'a str, FormatString, E>
 where
     E: ParseError<&'a str> + FromExternalError<&'a str, ParseIntError>,
 {
-    map(
-        all_consuming(many0(alt((literal, expansion)))),
-        FormatString,
-    )(input)
+    map(all_consuming(many0(alt((literal, expansion)))), |parts| {
+        FormatString {
+            parts,
+            separation: InkSeparation::NAIVE,
+        }
+    })(input)
 }
 
 impl FromStr for FormatString {
@@ -125,80 +408,439 @@ impl FromStr for FormatString {
     }
 }
 
+/// Where and when a color was picked
+#[derive(Clone, Copy, Default)]
+pub struct Context {
+    /// Coordinates of the pointer on the root window
+    pub position: (i16, i16),
+    pub screen: i32,
+    /// Seconds since the Unix epoch
+    pub time: u64,
+}
+
 pub trait FormatColor {
-    fn format(&self, color: ARGB) -> String;
+    /// Formats a color whose components are in `space`, as sampled from a
+    /// display that does not use sRGB, along with where it was picked.
+    fn format_at(&self, color: ARGB16, space: &ColorSpace, context: &Context) -> String;
+
+    fn format_in(&self, color: ARGB16, space: &ColorSpace) -> String {
+        self.format_at(color, space, &Context::default())
+    }
+
+    fn format(&self, color: ARGB16) -> String {
+        self.format_in(color, &ColorSpace::SRGB)
+    }
+}
+
+// What the parts of a format string are expanded from. Channels of models
+// derived from XYZ use the XYZ of the sampled color, which may be outside of
+// sRGB.
+struct Sample {
+    color: ARGB16,
+    xyz: XYZ,
+    separation: InkSeparation,
+    context: Context,
 }
 
 impl Channel {
-    fn extract(&self, color: ARGB) -> u8 {
+    /// The span of the values of the channel, which float and percentage
+    /// formats are relative to. Channels that are scaled for integer output
+    /// are scaled back to their usual range.
+    fn scale(&self) -> f32 {
+        match self {
+            Channel::R
+            | Channel::G
+            | Channel::B
+            | Channel::A
+            | Channel::P3R
+            | Channel::P3G
+            | Channel::P3B
+            | Channel::Rec2020R
+            | Channel::Rec2020G
+            | Channel::Rec2020B
+            | Channel::SimulatedR(_)
+            | Channel::SimulatedG(_)
+            | Channel::SimulatedB(_) => 255.0,
+            Channel::HslH | Channel::HsvH | Channel::LchH | Channel::OkLchH | Channel::HwbH => {
+                360.0
+            }
+            Channel::CctK
+            | Channel::Ansi16
+            | Channel::Ansi256
+            | Channel::Sgr16
+            | Channel::X
+            | Channel::Y
+            | Channel::Screen
+            | Channel::Wcag(_) => 1.0,
+            Channel::CctDuv => 1000.0,
+            _ => 100.0,
+        }
+    }
+
+    /// Whether the values of the channel stay within 0 and its scale, which
+    /// is required to scale them to a bit depth.
+    fn is_bounded(&self) -> bool {
+        self.scale() == 255.0
+            || self.is_hue()
+            || matches!(
+                self,
+                Channel::HslS
+                    | Channel::HslL
+                    | Channel::HsvS
+                    | Channel::HsvV
+                    | Channel::HwbW
+                    | Channel::HwbB
+                    | Channel::CmykC
+                    | Channel::CmykM
+                    | Channel::CmykY
+                    | Channel::CmykK
+                    | Channel::LabL
+                    | Channel::LchL
+                    | Channel::OkLabL
+                    | Channel::OkLchL
+            )
+    }
+
+    /// The factor that `extract` is multiplied with for integer output. Float
+    /// and percentage formats print the extracted value itself when this is
+    /// the scale of the channel.
+    fn unit(&self) -> f32 {
+        match self {
+            Channel::XyzX
+            | Channel::XyzY
+            | Channel::XyzZ
+            | Channel::OkLabL
+            | Channel::OkLabA
+            | Channel::OkLabB
+            | Channel::OkLchL
+            | Channel::OkLchC => 100.0,
+            Channel::P3R
+            | Channel::P3G
+            | Channel::P3B
+            | Channel::Rec2020R
+            | Channel::Rec2020G
+            | Channel::Rec2020B => 255.0,
+            Channel::CctDuv => 1000.0,
+            _ => 1.0,
+        }
+    }
+
+    /// The number of decimals printed when none are asked for
+    fn precision(&self) -> Option<usize> {
+        match self {
+            Channel::Wcag(_) => Some(2),
+            Channel::Apca(_) => Some(1),
+            _ => None,
+        }
+    }
+
+    fn is_hue(&self) -> bool {
+        matches!(
+            self,
+            Channel::HslH | Channel::HsvH | Channel::HwbH | Channel::LchH | Channel::OkLchH
+        )
+    }
+
+    fn extract(&self, sample: &Sample) -> f32 {
+        let Sample {
+            color,
+            xyz,
+            separation,
+            context,
+        } = *sample;
+        let simulated = |deficiency: &Deficiency| ARGB::from(deficiency.simulate(color));
         match self {
-            Channel::R => color.r,
-            Channel::G => color.g,
-            Channel::B => color.b,
+            Channel::R => f32::from(color.r) / 257.0,
+            Channel::G => f32::from(color.g) / 257.0,
+            Channel::B => f32::from(color.b) / 257.0,
+            Channel::A => f32::from(color.a) / 257.0,
+            Channel::HslH => HSL::from_rgb(color).h,
+            Channel::HslS => HSL::from_rgb(color).s,
+            Channel::HslL => HSL::from_rgb(color).l,
+            Channel::HsvH => HSV::from_rgb(color).h,
+            Channel::HsvS => HSV::from_rgb(color).s,
+            Channel::HsvV => HSV::from_rgb(color).v,
+            Channel::LabL => Lab::from_xyz(xyz).l,
+            Channel::LabA => Lab::from_xyz(xyz).a,
+            Channel::LabB => Lab::from_xyz(xyz).b,
+            Channel::LchL => LCh::from_lab(Lab::from_xyz(xyz)).l,
+            Channel::LchC => LCh::from_lab(Lab::from_xyz(xyz)).c,
+            Channel::LchH => LCh::from_lab(Lab::from_xyz(xyz)).h,
+            Channel::XyzX => xyz.x,
+            Channel::XyzY => xyz.y,
+            Channel::XyzZ => xyz.z,
+            Channel::OkLabL => OkLab::from_xyz(xyz).l,
+            Channel::OkLabA => OkLab::from_xyz(xyz).a,
+            Channel::OkLabB => OkLab::from_xyz(xyz).b,
+            Channel::OkLchL => OkLCh::from_oklab(OkLab::from_xyz(xyz)).l,
+            Channel::OkLchC => OkLCh::from_oklab(OkLab::from_xyz(xyz)).c,
+            Channel::OkLchH => OkLCh::from_oklab(OkLab::from_xyz(xyz)).h,
+            Channel::CmykC => CMYK::from_rgb(color, separation).c,
+            Channel::CmykM => CMYK::from_rgb(color, separation).m,
+            Channel::CmykY => CMYK::from_rgb(color, separation).y,
+            Channel::CmykK => CMYK::from_rgb(color, separation).k,
+            Channel::HwbH => HWB::from_rgb(color).h,
+            Channel::HwbW => HWB::from_rgb(color).w,
+            Channel::HwbB => HWB::from_rgb(color).b,
+            Channel::CctK => CCT::from_xyz(xyz).kelvin,
+            Channel::CctDuv => CCT::from_xyz(xyz).duv,
+            Channel::P3R => ColorSpace::DISPLAY_P3.encode(xyz)[0],
+            Channel::P3G => ColorSpace::DISPLAY_P3.encode(xyz)[1],
+            Channel::P3B => ColorSpace::DISPLAY_P3.encode(xyz)[2],
+            Channel::Rec2020R => ColorSpace::REC2020.encode(xyz)[0],
+            Channel::Rec2020G => ColorSpace::REC2020.encode(xyz)[1],
+            Channel::Rec2020B => ColorSpace::REC2020.encode(xyz)[2],
+            Channel::Ansi16 => f32::from(ansi::nearest_16(color)),
+            Channel::Ansi256 => f32::from(ansi::nearest_256(color)),
+            Channel::Sgr16 => f32::from(ansi::sgr_16(ansi::nearest_16(color))),
+            Channel::SimulatedR(deficiency) => f32::from(simulated(deficiency).r),
+            Channel::SimulatedG(deficiency) => f32::from(simulated(deficiency).g),
+            Channel::SimulatedB(deficiency) => f32::from(simulated(deficiency).b),
+            Channel::X => f32::from(context.position.0),
+            Channel::Y => f32::from(context.position.1),
+            Channel::Screen => context.screen as f32,
+            Channel::Wcag(background) => contrast_ratio(color, background.color()),
+            Channel::Apca(background) => apca_contrast(color, background.color()),
         }
     }
 }
 
 impl NumberFormat {
-    fn format<T>(&self, value: T) -> String
-    where
-        T: fmt::LowerHex + fmt::UpperHex + fmt::Octal + fmt::Binary + fmt::Display,
-    {
+    /// Whether the number of decimals can be chosen
+    fn has_precision(self) -> bool {
+        matches!(
+            self,
+            NumberFormat::Decimal | NumberFormat::Float | NumberFormat::Percentage
+        )
+    }
+
+    /// Formats the value of a channel. Float and percentage formats print
+    /// `fraction`, the value relative to the scale of the channel, and integer
+    /// formats round the value.
+    fn format(&self, value: f32, fraction: f32, precision: Option<usize>) -> String {
+        let integer = value.round() as i32;
+        let sign = if integer < 0 { "-" } else { "" };
+        let magnitude = integer.unsigned_abs();
         match self {
-            NumberFormat::LowercaseHex => format!("{:x}", value),
-            NumberFormat::UppercaseHex => format!("{:X}", value),
-            NumberFormat::Octal => format!("{:o}", value),
-            NumberFormat::Binary => format!("{:b}", value),
-            NumberFormat::Decimal => format!("{}", value),
+            NumberFormat::LowercaseHex => format!("{}{:x}", sign, magnitude),
+            NumberFormat::UppercaseHex => format!("{}{:X}", sign, magnitude),
+            NumberFormat::Octal => format!("{}{:o}", sign, magnitude),
+            NumberFormat::Binary => format!("{}{:b}", sign, magnitude),
+            NumberFormat::Decimal => match precision {
+                Some(decimals) => fixed(value, decimals),
+                None => format!("{}{}", sign, magnitude),
+            },
+            NumberFormat::Float => match precision {
+                Some(decimals) => fixed(fraction, decimals),
+                None => trimmed(fraction, 3),
+            },
+            NumberFormat::Percentage => {
+                format!("{}%", fixed(fraction * 100.0, precision.unwrap_or(0)))
+            }
+        }
+    }
+
+    /// Truncates the value and fraction of a channel to the decimals that are
+    /// printed, so that formatting rounds them down.
+    fn truncate(&self, value: f32, fraction: f32, precision: Option<usize>) -> (f32, f32) {
+        match self {
+            NumberFormat::Decimal => (truncated(value, precision.unwrap_or(0)), fraction),
+            NumberFormat::Float => (value, truncated(fraction, precision.unwrap_or(3))),
+            NumberFormat::Percentage => {
+                let percentage = truncated(fraction * 100.0, precision.unwrap_or(0));
+                (value, percentage / 100.0)
+            }
+            _ => (truncated(value, 0), fraction),
+        }
+    }
+
+    /// Formats a hue in degrees. Hues that would be printed as a full turn are
+    /// printed as 0 instead.
+    fn format_hue(&self, hue: f32, precision: Option<usize>) -> String {
+        let formatted = self.format(hue, hue / 360.0, precision);
+        if formatted == self.format(360.0, 1.0, precision) {
+            self.format(0.0, 0.0, precision)
+        } else {
+            formatted
+        }
+    }
+}
+
+/// Formats `value` with at most `decimals` decimals, dropping trailing zeros.
+fn trimmed(value: f32, decimals: usize) -> String {
+    let formatted = fixed(value, decimals);
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_owned()
+}
+
+/// Truncates `value` to `decimals` decimals.
+fn truncated(value: f32, decimals: usize) -> f32 {
+    let factor = 10f32.powi(decimals as i32);
+    // Allow for rounding errors so that e.g. black on white stays at 21
+    (value * factor + 1e-3).floor() / factor
+}
+
+impl Background {
+    fn color(self) -> ARGB {
+        match self {
+            Background::White => ARGB::WHITE,
+            Background::Black => ARGB::BLACK,
+        }
+    }
+}
+
+/// Formats `value` with a fixed number of decimals. Values that round to zero
+/// are printed without a sign.
+fn fixed(value: f32, decimals: usize) -> String {
+    let formatted = format!("{:.*}", decimals, value);
+    match formatted.strip_prefix('-') {
+        Some(unsigned) if unsigned.chars().all(|c| c == '0' || c == '.') => unsigned.to_owned(),
+        _ => formatted,
+    }
+}
+
+/// Pads `base` on the left up to the length of the padding.
+fn padded(base: String, pad: Option<Pad>) -> String {
+    if let Some(Pad { char, len }) = pad {
+        let base_len = base.chars().count();
+        if let Some(pad_len) = (len as usize).checked_sub(base_len) {
+            let mut padded: String = iter::repeat_n(char, pad_len).collect();
+            padded.push_str(&base);
+            return padded;
         }
     }
+    base
 }
 
-impl FormatColor for FormatPart {
-    fn format(&self, color: ARGB) -> String {
+impl FormatPart {
+    fn format(&self, sample: &Sample) -> String {
+        let color = sample.color;
         match self {
             FormatPart::Literal(s) => s.clone(),
             FormatPart::Expansion {
                 channel,
                 format,
+                precision,
+                bits,
                 pad,
             } => {
-                let value = channel.extract(color);
-                let base = format.format(value);
-                if let Some(Pad { char, len }) = *pad {
-                    let base_len = base.chars().count();
-                    if let Some(pad_len) = (len as usize).checked_sub(base_len) {
-                        let mut padded: String = iter::repeat(char).take(pad_len).collect();
-                        padded.push_str(&base);
-                        return padded;
+                let extracted = channel.extract(sample);
+                let (unit, scale) = (channel.unit(), channel.scale());
+                // Dividing the unit back out could change the last decimal
+                let fraction = if unit == scale {
+                    extracted
+                } else {
+                    extracted * unit / scale
+                };
+                let (value, fraction) = match *bits {
+                    Some(bits) => {
+                        let max = ((1u32 << bits) - 1) as f32;
+                        let fraction = fraction.clamp(0.0, 1.0);
+                        (fraction * max, fraction)
                     }
-                }
-                base
+                    None => (extracted * unit, fraction),
+                };
+                let precision = match format {
+                    NumberFormat::Decimal => precision.or_else(|| channel.precision()),
+                    _ => *precision,
+                };
+                // Contrast ratios must never appear to pass a threshold they
+                // do not meet
+                let (value, fraction) = match channel {
+                    Channel::Wcag(_) => format.truncate(value, fraction, precision),
+                    _ => (value, fraction),
+                };
+                let formatted = if channel.is_hue() && bits.is_none() {
+                    format.format_hue(value, precision)
+                } else {
+                    format.format(value, fraction, precision)
+                };
+                padded(formatted, *pad)
             }
+            FormatPart::Name(pad) => padded(names::nearest(color).to_owned(), *pad),
+            FormatPart::TextColor(pad) => {
+                let text = if ARGB::from(color).is_dark() {
+                    "white"
+                } else {
+                    "black"
+                };
+                padded(text.to_owned(), *pad)
+            }
+            FormatPart::Color(format) => format.format(color),
+            FormatPart::Time => sample.context.time.to_string(),
         }
     }
 }
 
+impl FormatString {
+    pub fn with_ink_separation(self, separation: InkSeparation) -> FormatString {
+        FormatString { separation, ..self }
+    }
+}
+
 impl FormatColor for FormatString {
-    fn format(&self, color: ARGB) -> String {
-        self.0.iter().map(|part| part.format(color)).collect()
+    fn format_at(&self, color: ARGB16, space: &ColorSpace, context: &Context) -> String {
+        let sample = Sample {
+            color: space.to_srgb(color),
+            xyz: space.to_xyz(color),
+            separation: self.separation,
+            context: *context,
+        };
+        self.parts.iter().map(|part| part.format(&sample)).collect()
     }
 }
 
 // Formatting Shortcuts
 
-#[derive(PartialEq)]
+#[derive(Clone, PartialEq)]
 pub enum HexCompaction {
     Compact,
     Full,
 }
 
+#[derive(Clone)]
 pub enum Format {
     LowercaseHex(HexCompaction),
     UppercaseHex(HexCompaction),
+    LowercaseHexAlpha(HexCompaction),
+    UppercaseHexAlpha(HexCompaction),
+    LowercaseAlphaHex,
+    UppercaseAlphaHex,
     Plain,
     RGB,
+    RGBA,
     HSL,
+    HSV,
+    Lab,
+    LCh,
+    XYZ,
+    OkLab,
+    OkLCh,
+    CMYK(InkSeparation),
+    HWB,
+    Name,
+    Apca,
+    Simulated(Deficiency),
+    Ansi16,
+    Ansi256,
+    Sgr16,
+    Sgr256,
+    Sgr24,
+    Kelvin,
+    DisplayP3,
+    Rec2020,
+    X11,
+}
+
+impl Format {
+    pub fn with_ink_separation(self, separation: InkSeparation) -> Format {
+        match self {
+            Format::CMYK(_) => Format::CMYK(separation),
+            format => format,
+        }
+    }
 }
 
 impl FromStr for Format {
@@ -209,16 +851,59 @@ impl FromStr for Format {
             "HEX" => Ok(Format::UppercaseHex(HexCompaction::Full)),
             "hex!" => Ok(Format::LowercaseHex(HexCompaction::Compact)),
             "HEX!" => Ok(Format::UppercaseHex(HexCompaction::Compact)),
+            "hexa" => Ok(Format::LowercaseHexAlpha(HexCompaction::Full)),
+            "HEXA" => Ok(Format::UppercaseHexAlpha(HexCompaction::Full)),
+            "hexa!" => Ok(Format::LowercaseHexAlpha(HexCompaction::Compact)),
+            "HEXA!" => Ok(Format::UppercaseHexAlpha(HexCompaction::Compact)),
+            "ahex" => Ok(Format::LowercaseAlphaHex),
+            "AHEX" => Ok(Format::UppercaseAlphaHex),
             "plain" => Ok(Format::Plain),
             "rgb" => Ok(Format::RGB),
+            "rgba" => Ok(Format::RGBA),
             "hsl" => Ok(Format::HSL),
+            "hsv" => Ok(Format::HSV),
+            "lab" => Ok(Format::Lab),
+            "lch" => Ok(Format::LCh),
+            "xyz" => Ok(Format::XYZ),
+            "oklab" => Ok(Format::OkLab),
+            "oklch" => Ok(Format::OkLCh),
+            "cmyk" => Ok(Format::CMYK(InkSeparation::NAIVE)),
+            "hwb" => Ok(Format::HWB),
+            "name" => Ok(Format::Name),
+            "apca" => Ok(Format::Apca),
+            "protanopia" => Ok(Format::Simulated(Deficiency::Protanopia)),
+            "deuteranopia" => Ok(Format::Simulated(Deficiency::Deuteranopia)),
+            "tritanopia" => Ok(Format::Simulated(Deficiency::Tritanopia)),
+            "achromatopsia" => Ok(Format::Simulated(Deficiency::Achromatopsia)),
+            "ansi16" => Ok(Format::Ansi16),
+            "ansi256" => Ok(Format::Ansi256),
+            "sgr16" => Ok(Format::Sgr16),
+            "sgr256" => Ok(Format::Sgr256),
+            "sgr24" => Ok(Format::Sgr24),
+            "kelvin" => Ok(Format::Kelvin),
+            "p3" => Ok(Format::DisplayP3),
+            "rec2020" => Ok(Format::Rec2020),
+            "x11" => Ok(Format::X11),
             _ => Err(anyhow!("Invalid format")),
         }
     }
 }
 
+// Formats a color with the CSS `color()` function
+fn css_color(name: &str, [r, g, b]: [f32; 3]) -> String {
+    format!(
+        "color({} {} {} {})",
+        name,
+        fixed(r, 4),
+        fixed(g, 4),
+        fixed(b, 4)
+    )
+}
+
 impl FormatColor for Format {
-    fn format(&self, color: ARGB) -> String {
+    fn format_at(&self, sampled: ARGB16, space: &ColorSpace, _: &Context) -> String {
+        let deep = space.to_srgb(sampled);
+        let color = ARGB::from(deep);
         match self {
             Format::LowercaseHex(comp) => {
                 if *comp == HexCompaction::Compact && color.is_compactable() {
@@ -234,17 +919,190 @@ impl FormatColor for Format {
                     format!("#{:02X}{:02X}{:02X}", color.r, color.g, color.b)
                 }
             }
+            Format::LowercaseHexAlpha(comp) => {
+                if *comp == HexCompaction::Compact && color.is_compactable_with_alpha() {
+                    format!(
+                        "#{:x}{:x}{:x}{:x}",
+                        color.r & 0xf,
+                        color.g & 0xf,
+                        color.b & 0xf,
+                        color.a & 0xf
+                    )
+                } else {
+                    format!(
+                        "#{:02x}{:02x}{:02x}{:02x}",
+                        color.r, color.g, color.b, color.a
+                    )
+                }
+            }
+            Format::UppercaseHexAlpha(comp) => {
+                if *comp == HexCompaction::Compact && color.is_compactable_with_alpha() {
+                    format!(
+                        "#{:X}{:X}{:X}{:X}",
+                        color.r & 0xf,
+                        color.g & 0xf,
+                        color.b & 0xf,
+                        color.a & 0xf
+                    )
+                } else {
+                    format!(
+                        "#{:02X}{:02X}{:02X}{:02X}",
+                        color.r, color.g, color.b, color.a
+                    )
+                }
+            }
+            Format::LowercaseAlphaHex => format!(
+                "#{:02x}{:02x}{:02x}{:02x}",
+                color.a, color.r, color.g, color.b
+            ),
+            Format::UppercaseAlphaHex => format!(
+                "#{:02X}{:02X}{:02X}{:02X}",
+                color.a, color.r, color.g, color.b
+            ),
             Format::Plain => format!("{};{};{}", color.r, color.g, color.b),
             Format::RGB => format!("rgb({}, {}, {})", color.r, color.g, color.b),
+            Format::RGBA => format!(
+                "rgba({}, {}, {}, {})",
+                color.r,
+                color.g,
+                color.b,
+                trimmed(f32::from(color.a) / 255.0, 3)
+            ),
             Format::HSL => {
-                let hsl = HSL::from_rgb(color);
+                let hsl = HSL::from_rgb(deep);
 
-                format!("hsl({}, {}%, {}%)", hsl.h, hsl.s, hsl.l)
-            },
+                format!(
+                    "hsl({}, {}%, {}%)",
+                    NumberFormat::Decimal.format_hue(hsl.h, None),
+                    hsl.s.round(),
+                    hsl.l.round()
+                )
+            }
+            Format::HSV => {
+                let hsv = HSV::from_rgb(deep);
+
+                format!(
+                    "hsv({}, {}%, {}%)",
+                    NumberFormat::Decimal.format_hue(hsv.h, None),
+                    hsv.s.round(),
+                    hsv.v.round()
+                )
+            }
+            Format::Lab => {
+                let lab = Lab::from_xyz(space.to_xyz(sampled));
+
+                format!(
+                    "lab({}, {}, {})",
+                    fixed(lab.l, 2),
+                    fixed(lab.a, 2),
+                    fixed(lab.b, 2)
+                )
+            }
+            Format::LCh => {
+                let lch = LCh::from_lab(Lab::from_xyz(space.to_xyz(sampled)));
+
+                format!(
+                    "lch({}, {}, {})",
+                    fixed(lch.l, 2),
+                    fixed(lch.c, 2),
+                    NumberFormat::Decimal.format_hue(lch.h, Some(2))
+                )
+            }
+            Format::XYZ => {
+                let xyz = space.to_xyz(sampled);
+
+                format!(
+                    "xyz({}, {}, {})",
+                    fixed(xyz.x * 100.0, 2),
+                    fixed(xyz.y * 100.0, 2),
+                    fixed(xyz.z * 100.0, 2)
+                )
+            }
+            Format::OkLab => {
+                let oklab = OkLab::from_xyz(space.to_xyz(sampled));
+
+                format!(
+                    "oklab({}% {} {})",
+                    fixed(oklab.l * 100.0, 2),
+                    fixed(oklab.a, 4),
+                    fixed(oklab.b, 4)
+                )
+            }
+            Format::OkLCh => {
+                let oklch = OkLCh::from_oklab(OkLab::from_xyz(space.to_xyz(sampled)));
+
+                format!(
+                    "oklch({}% {} {})",
+                    fixed(oklch.l * 100.0, 2),
+                    fixed(oklch.c, 4),
+                    NumberFormat::Decimal.format_hue(oklch.h, Some(2))
+                )
+            }
+            Format::CMYK(separation) => {
+                let cmyk = CMYK::from_rgb(deep, *separation);
+
+                format!(
+                    "cmyk({}%, {}%, {}%, {}%)",
+                    cmyk.c.round(),
+                    cmyk.m.round(),
+                    cmyk.y.round(),
+                    cmyk.k.round()
+                )
+            }
+            Format::HWB => {
+                let hwb = HWB::from_rgb(deep);
+
+                format!(
+                    "hwb({} {}% {}%)",
+                    NumberFormat::Decimal.format_hue(hwb.h, None),
+                    hwb.w.round(),
+                    hwb.b.round()
+                )
+            }
+            Format::Name => names::nearest(deep).to_owned(),
+            Format::Apca => format!(
+                "Lc {} on white, Lc {} on black",
+                fixed(apca_contrast(deep, ARGB::WHITE), 1),
+                fixed(apca_contrast(deep, ARGB::BLACK), 1)
+            ),
+            Format::Simulated(deficiency) => {
+                let simulated = ARGB::from(deficiency.simulate(deep));
+
+                format!("#{:02x}{:02x}{:02x}", simulated.r, simulated.g, simulated.b)
+            }
+            Format::Ansi16 => format!("{}", ansi::nearest_16(deep)),
+            Format::Ansi256 => format!("{}", ansi::nearest_256(deep)),
+            Format::Sgr16 => format!("{}", ansi::sgr_16(ansi::nearest_16(deep))),
+            Format::Sgr256 => format!("38;5;{}", ansi::nearest_256(deep)),
+            Format::Sgr24 => format!("38;2;{};{};{}", color.r, color.g, color.b),
+            Format::Kelvin => {
+                let cct = CCT::from_xyz(space.to_xyz(sampled));
+
+                format!("{}K (Duv {})", cct.kelvin.round(), fixed(cct.duv, 4))
+            }
+            Format::DisplayP3 => css_color(
+                "display-p3",
+                ColorSpace::DISPLAY_P3.encode(space.to_xyz(sampled)),
+            ),
+            Format::Rec2020 => {
+                css_color("rec2020", ColorSpace::REC2020.encode(space.to_xyz(sampled)))
+            }
+            Format::X11 => format!("rgb:{:04x}/{:04x}/{:04x}", deep.r, deep.g, deep.b),
         }
     }
 }
 
+/// Describes how far apart two colors are using each of the supported color
+/// difference metrics.
+pub fn format_difference(first: Lab, second: Lab) -> String {
+    format!(
+        "ΔE00 {}, ΔE94 {}, ΔE76 {}",
+        fixed(first.delta_e2000(&second), 2),
+        fixed(first.delta_e94(&second), 2),
+        fixed(first.delta_e76(&second), 2)
+    )
+}
+
 // Tests
 
 #[test]
@@ -286,6 +1144,87 @@ fn test_expansion() {
         _ => panic!(),
     }
 
+    match expansion::<()>("%{hsv.h}").unwrap().1 {
+        FormatPart::Expansion {
+            channel: Channel::HsvH,
+            format: NumberFormat::Decimal,
+            ..
+        } => (),
+        _ => panic!(),
+    }
+
+    match expansion::<()>("%{03hhsv.s}").unwrap().1 {
+        FormatPart::Expansion {
+            channel: Channel::HsvS,
+            format: NumberFormat::LowercaseHex,
+            precision: None,
+            bits: None,
+            pad: Some(Pad { char: '0', len: 3 }),
+        } => (),
+        _ => panic!(),
+    }
+
+    match expansion::<()>("%{08.3fr}").unwrap().1 {
+        FormatPart::Expansion {
+            channel: Channel::R,
+            format: NumberFormat::Float,
+            precision: Some(3),
+            bits: None,
+            pad: Some(Pad { char: '0', len: 8 }),
+        } => (),
+        _ => panic!(),
+    }
+
+    match expansion::<()>("%{.2lab.l}").unwrap().1 {
+        FormatPart::Expansion {
+            channel: Channel::LabL,
+            format: NumberFormat::Decimal,
+            precision: Some(2),
+            bits: None,
+            pad: None,
+        } => (),
+        _ => panic!(),
+    }
+
+    match expansion::<()>("%{04hr:16}").unwrap().1 {
+        FormatPart::Expansion {
+            channel: Channel::R,
+            format: NumberFormat::LowercaseHex,
+            precision: None,
+            bits: Some(16),
+            pad: Some(Pad { char: '0', len: 4 }),
+        } => (),
+        _ => panic!(),
+    }
+
+    assert!(expansion::<()>("%{.2hr}").is_err());
+    assert!(expansion::<()>("%{.r}").is_err());
+    assert!(expansion::<()>("%{.9fr}").is_ok());
+    assert!(expansion::<()>("%{.10fr}").is_err());
+    assert!(expansion::<()>("%{.18446744073709551615fr}").is_err());
+
+    // Padding characters that are channels themselves
+    match expansion::<()>("%{b8r}").unwrap().1 {
+        FormatPart::Expansion {
+            channel: Channel::R,
+            pad: Some(Pad { char: 'b', len: 8 }),
+            ..
+        } => (),
+        _ => panic!(),
+    }
+    match expansion::<()>("%{r3Hg}").unwrap().1 {
+        FormatPart::Expansion {
+            channel: Channel::G,
+            format: NumberFormat::UppercaseHex,
+            pad: Some(Pad { char: 'r', len: 3 }),
+            ..
+        } => (),
+        _ => panic!(),
+    }
+    assert!(expansion::<()>("%{r:0}").is_err());
+    assert!(expansion::<()>("%{r:25}").is_err());
+    assert!(expansion::<()>("%{r:}").is_err());
+
     match expansion::<()>("%%").unwrap().1 {
         FormatPart::Literal(ref s) if s == "%" => (),
         _ => panic!(),
@@ -297,7 +1236,9 @@ fn test_format_color() {
     let string: Result<FormatString, _> = "".parse();
     assert!(string.is_ok());
 
-    let should_err = vec!["%{}", "%}", "%{gg}", "%%%{-a}", "%a{}", "%foo"];
+    let should_err = vec![
+        "%{}", "%}", "%{gg}", "%%%{-a}", "%a{}", "%foo", "%{hsv}", "%{hsv.x}",
+    ];
     for case in should_err {
         assert!(case.parse::<FormatString>().is_err());
     }
@@ -306,26 +1247,595 @@ fn test_format_color() {
 #[test]
 fn test_examples_from_readme() {
     let fmt: FormatString = "#%{02hr}%{02hg}%{02hb}".parse().unwrap();
-    assert_eq!(fmt.format(ARGB::new(0xff, 255, 0, 255)), "#ff00ff");
+    assert_eq!(fmt.format(ARGB::new(0xff, 255, 0, 255).into()), "#ff00ff");
 
     let fmt: FormatString = "#%{02Hr}%{02Hg}%{02Hb}".parse().unwrap();
-    assert_eq!(fmt.format(ARGB::new(0xff, 0, 255, 0)), "#00FF00");
+    assert_eq!(fmt.format(ARGB::new(0xff, 0, 255, 0).into()), "#00FF00");
 
     let fmt: FormatString = "rgb(%{r}, %{g}, %{b})".parse().unwrap();
     assert_eq!(
-        fmt.format(ARGB::new(0xff, 255, 255, 255)),
+        fmt.format(ARGB::new(0xff, 255, 255, 255).into()),
         "rgb(255, 255, 255)"
     );
 
     let fmt: FormatString = "%{r};%{g};%{b}".parse().unwrap();
-    assert_eq!(fmt.format(ARGB::new(0xff, 0, 0, 0)), "0;0;0");
+    assert_eq!(fmt.format(ARGB::new(0xff, 0, 0, 0).into()), "0;0;0");
 
     let fmt: FormatString = "%{r}, %{g}, %{b}".parse().unwrap();
-    assert_eq!(fmt.format(ARGB::new(0xff, 0, 0, 0)), "0, 0, 0");
+    assert_eq!(fmt.format(ARGB::new(0xff, 0, 0, 0).into()), "0, 0, 0");
 
     let fmt: FormatString = "Green: %{-4g}".parse().unwrap();
-    assert_eq!(fmt.format(ARGB::new(0xff, 0, 7, 0)), "Green: ---7");
+    assert_eq!(fmt.format(ARGB::new(0xff, 0, 7, 0).into()), "Green: ---7");
 
     let fmt: FormatString = "%{016Br}".parse().unwrap();
-    assert_eq!(fmt.format(ARGB::new(0xff, 3, 0, 0)), "0000000000000011");
+    assert_eq!(
+        fmt.format(ARGB::new(0xff, 3, 0, 0).into()),
+        "0000000000000011"
+    );
+}
+
+#[test]
+fn test_hsv() {
+    let color = ARGB::new(0xff, 14, 115, 123);
+
+    let fmt: Format = "hsv".parse().unwrap();
+    assert_eq!(fmt.format(color.into()), "hsv(184, 89%, 48%)");
+
+    let fmt: FormatString = "hsv(%{hsv.h}, %{hsv.s}%%, %{hsv.v}%%)".parse().unwrap();
+    assert_eq!(fmt.format(color.into()), "hsv(184, 89%, 48%)");
+
+    // Hues that round up to 360 wrap around to 0
+    let color = ARGB::new(0xff, 255, 0, 1).into();
+    assert_eq!(Format::HSV.format(color), "hsv(0, 100%, 100%)");
+    assert_eq!(Format::HWB.format(color), "hwb(0 0% 0%)");
+    let fmt: FormatString = "%{hsv.h} %{hwb.h} %{.1hsl.h} %{.2fhsv.h} %{.2hsv.h}"
+        .parse()
+        .unwrap();
+    assert_eq!(fmt.format(color), "0 0 359.8 0.00 359.76");
+}
+
+#[test]
+fn test_hsl() {
+    let color = ARGB::new(0xff, 14, 115, 123);
+
+    let fmt: Format = "hsl".parse().unwrap();
+    assert_eq!(fmt.format(color.into()), "hsl(184, 80%, 27%)");
+
+    let fmt: FormatString = "hsl(%{hsl.h}, %{hsl.s}%%, %{hsl.l}%%)".parse().unwrap();
+    assert_eq!(fmt.format(color.into()), "hsl(184, 80%, 27%)");
+}
+
+#[test]
+fn test_fixed() {
+    assert_eq!(fixed(1.005, 1), "1.0");
+    assert_eq!(fixed(-12.345, 2), "-12.35");
+    assert_eq!(fixed(-0.0001, 2), "0.00");
+    assert_eq!(fixed(-0.4, 0), "0");
+}
+
+#[test]
+fn test_format_hue() {
+    let decimal = NumberFormat::Decimal;
+    assert_eq!(decimal.format_hue(359.996, Some(2)), "0.00");
+    assert_eq!(decimal.format_hue(359.994, Some(2)), "359.99");
+    assert_eq!(decimal.format_hue(359.6, None), "0");
+    assert_eq!(NumberFormat::Percentage.format_hue(358.5, None), "0%");
+    assert_eq!(NumberFormat::Float.format_hue(359.9, Some(2)), "0.00");
+    assert_eq!(NumberFormat::LowercaseHex.format_hue(359.5, None), "0");
+}
+
+#[test]
+fn test_cie() {
+    let red = ARGB::new(0xff, 0xff, 0, 0);
+    let teal = ARGB::new(0xff, 14, 115, 123);
+
+    let fmt: Format = "lab".parse().unwrap();
+    assert_eq!(fmt.format(red.into()), "lab(53.24, 80.09, 67.20)");
+    assert_eq!(fmt.format(ARGB::WHITE.into()), "lab(100.00, 0.00, 0.00)");
+
+    let fmt: Format = "lch".parse().unwrap();
+    assert_eq!(fmt.format(teal.into()), "lch(43.92, 26.07, 207.43)");
+    assert_eq!(fmt.format(ARGB::WHITE.into()), "lch(100.00, 0.00, 0.00)");
+
+    let fmt: Format = "xyz".parse().unwrap();
+    assert_eq!(fmt.format(red.into()), "xyz(41.24, 21.26, 1.93)");
+
+    let fmt: FormatString = "%{lab.l} %{lab.a} %{lab.b} %{lch.h}".parse().unwrap();
+    assert_eq!(fmt.format(teal.into()), "44 -23 -12 207");
+
+    let fmt: FormatString = "%{hlab.a} %{xyz.x}/%{xyz.y}/%{xyz.z}".parse().unwrap();
+    assert_eq!(fmt.format(teal.into()), "-17 10/14/21");
+}
+
+#[test]
+fn test_oklab() {
+    let red = ARGB::new(0xff, 0xff, 0, 0);
+
+    let fmt: Format = "oklab".parse().unwrap();
+    assert_eq!(fmt.format(red.into()), "oklab(62.80% 0.2249 0.1258)");
+    assert_eq!(
+        fmt.format(ARGB::WHITE.into()),
+        "oklab(100.00% 0.0000 0.0000)"
+    );
+
+    let fmt: Format = "oklch".parse().unwrap();
+    assert_eq!(fmt.format(red.into()), "oklch(62.80% 0.2577 29.23)");
+    assert_eq!(fmt.format(ARGB::BLACK.into()), "oklch(0.00% 0.0000 0.00)");
+
+    let fmt: FormatString = "%{oklch.l} %{oklch.c} %{oklch.h} %{oklab.a}"
+        .parse()
+        .unwrap();
+    assert_eq!(fmt.format(red.into()), "63 26 29 22");
+}
+
+#[test]
+fn test_cmyk() {
+    let red = ARGB::new(0xff, 0xff, 0, 0);
+    let blue = ARGB::new(0xff, 0x33, 0x66, 0x99);
+
+    let fmt: Format = "cmyk".parse().unwrap();
+    assert_eq!(fmt.format(red.into()), "cmyk(0%, 100%, 100%, 0%)");
+    assert_eq!(fmt.format(blue.into()), "cmyk(67%, 33%, 0%, 40%)");
+
+    let separation = InkSeparation {
+        black_generation: 0.5,
+        under_color_removal: 1.0,
+    };
+    let fmt = fmt.with_ink_separation(separation);
+    assert_eq!(fmt.format(blue.into()), "cmyk(60%, 40%, 20%, 20%)");
+
+    let fmt: FormatString = "%{cmyk.c}/%{cmyk.m}/%{cmyk.y}/%{cmyk.k}".parse().unwrap();
+    assert_eq!(fmt.format(blue.into()), "67/33/0/40");
+    let fmt = fmt.with_ink_separation(separation);
+    assert_eq!(fmt.format(blue.into()), "60/40/20/20");
+}
+
+#[test]
+fn test_hwb() {
+    let color = ARGB::new(0xff, 14, 115, 123);
+
+    let fmt: Format = "hwb".parse().unwrap();
+    assert_eq!(fmt.format(color.into()), "hwb(184 5% 52%)");
+    assert_eq!(fmt.format(ARGB::WHITE.into()), "hwb(0 100% 0%)");
+
+    let fmt: FormatString = "hwb(%{hwb.h} %{hwb.w}%% %{hwb.b}%%)".parse().unwrap();
+    assert_eq!(fmt.format(color.into()), "hwb(184 5% 52%)");
+}
+
+#[test]
+fn test_name() {
+    let color = ARGB::new(0xff, 0x1e, 0x90, 0xfe).into();
+    assert_eq!(Format::Name.format(color), "dodgerblue");
+
+    let fmt: FormatString = "#%{02hr}%{02hg}%{02hb} (≈ %{name})".parse().unwrap();
+    assert_eq!(fmt.format(color), "#1e90fe (≈ dodgerblue)");
+
+    assert_eq!(
+        "%{-14name}|".parse::<FormatString>().unwrap().format(color),
+        "----dodgerblue|"
+    );
+    assert!("%{hname}".parse::<FormatString>().is_err());
+    assert!("%{.2name}".parse::<FormatString>().is_err());
+    assert!("%{name:8}".parse::<FormatString>().is_err());
+}
+
+#[test]
+fn test_contrast() {
+    let fmt: FormatString = "%{wcag.white}:1 %{wcag.black}:1 %{wcag.text}"
+        .parse()
+        .unwrap();
+    assert_eq!(fmt.format(ARGB::WHITE.into()), "1.00:1 21.00:1 black");
+    assert_eq!(fmt.format(ARGB::BLACK.into()), "21.00:1 1.00:1 white");
+    assert_eq!(
+        fmt.format(ARGB::new(0xff, 0x76, 0x76, 0x76).into()),
+        "4.54:1 4.62:1 black"
+    );
+    assert_eq!(
+        fmt.format(ARGB::new(0xff, 0, 0, 0xff).into()),
+        "8.59:1 2.44:1 white"
+    );
+
+    assert_eq!(fixed(truncated(4.499_9, 2), 2), "4.49");
+
+    let fmt: FormatString = "%{.1wcag.white} %{07.3wcag.black} %{-6wcag.text}"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        fmt.format(ARGB::new(0xff, 0x76, 0x76, 0x76).into()),
+        "4.5 004.623 -black"
+    );
+    assert!("%{wcag.white:8}".parse::<FormatString>().is_err());
+
+    // A ratio of 4.48 is truncated in every number format
+    let fmt: FormatString = "%{.1wcag.white} %{.1fwcag.white} %{%wcag.white} %{wcag.white}"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        fmt.format(ARGB::new(0xff, 0x77, 0x77, 0x77).into()),
+        "4.4 4.4 447% 4.47"
+    );
+    let fmt: FormatString = "%{.0wcag.white} %{hwcag.white}".parse().unwrap();
+    assert_eq!(fmt.format(ARGB::new(0xff, 0x76, 0x76, 0x76).into()), "4 4");
+    assert!("%{fwcag.text}".parse::<FormatString>().is_err());
+}
+
+#[test]
+fn test_apca() {
+    let gray = ARGB::new(0xff, 0x88, 0x88, 0x88).into();
+    assert_eq!(
+        Format::Apca.format(gray),
+        "Lc 63.1 on white, Lc -38.6 on black"
+    );
+
+    let fmt: FormatString = "%{apca.white} %{apca.black}".parse().unwrap();
+    assert_eq!(fmt.format(gray), "63.1 -38.6");
+    assert_eq!(fmt.format(ARGB::WHITE.into()), "0.0 -107.9");
+    assert_eq!(fmt.format(ARGB::BLACK.into()), "106.0 0.0");
+
+    let fmt: FormatString = "%{ 6.2apca.white}|%{apca.black}|%{fapca.white}"
+        .parse()
+        .unwrap();
+    assert_eq!(fmt.format(gray), " 63.06|-38.6|0.631");
+    assert!("%{apca.white:8}".parse::<FormatString>().is_err());
+}
+
+#[test]
+fn test_simulated() {
+    let red = ARGB::new(0xff, 0xff, 0, 0).into();
+    let format = |name: &str| name.parse::<Format>().unwrap().format(red);
+    assert_eq!(format("protanopia"), "#6d5f00");
+    assert_eq!(format("achromatopsia"), "#7f7f7f");
+    assert_eq!(format("deuteranopia"), "#a39000");
+    assert_eq!(format("tritanopia"), "#ff000f");
+
+    let fmt: FormatString = "#%{02hdeuteranopia.r}%{02hdeuteranopia.g}%{02hdeuteranopia.b}"
+        .parse()
+        .unwrap();
+    assert_eq!(fmt.format(red), "#a39000");
+    assert!("%{protanopia}".parse::<FormatString>().is_err());
+}
+
+#[test]
+fn test_ansi() {
+    let color = ARGB::new(0xff, 0x1e, 0x90, 0xff).into();
+    let format = |name: &str| name.parse::<Format>().unwrap().format(color);
+    assert_eq!(format("ansi16"), "12");
+    assert_eq!(format("ansi256"), "33");
+    assert_eq!(format("sgr16"), "94");
+    assert_eq!(format("sgr256"), "38;5;33");
+    assert_eq!(format("sgr24"), "38;2;30;144;255");
+
+    let fmt: FormatString = "%{ansi16} %{ansi256} %{sgr16} %{a}".parse().unwrap();
+    assert_eq!(fmt.format(color), "12 33 94 255");
+}
+
+#[test]
+fn test_kelvin() {
+    assert_eq!(
+        Format::Kelvin.format(ARGB::WHITE.into()),
+        "6506K (Duv 0.0033)"
+    );
+
+    let warm = ARGB::new(0xff, 0xff, 0xc0, 0x80).into();
+    let fmt: FormatString = "%{cct.k}K %{cct.duv}".parse().unwrap();
+    assert_eq!(fmt.format(ARGB::WHITE.into()), "6506K 3");
+    assert_eq!(fmt.format(warm), "3279K -1");
+
+    // Whites that sRGB cannot show keep their temperature
+    let p3 = ARGB16::new(0xffff, 0xffff, 0xe000, 0xa000);
+    let space = ColorSpace::DISPLAY_P3;
+    assert_eq!(Format::Kelvin.format_in(p3, &space), "3869K (Duv 0.0075)");
+    assert_eq!(
+        Format::Kelvin.format(space.to_srgb(p3)),
+        "4209K (Duv 0.0063)"
+    );
+    assert_eq!(fmt.format_in(p3, &space), "3869K 7");
+}
+
+#[test]
+fn test_format_difference() {
+    let red = Lab::from_rgb(ARGB::new(0xff, 0xff, 0, 0));
+    let dark_red = Lab::from_rgb(ARGB::new(0xff, 0xf0, 0, 0));
+    assert_eq!(
+        format_difference(red, red),
+        "ΔE00 0.00, ΔE94 0.00, ΔE76 0.00"
+    );
+    assert_eq!(
+        format_difference(red, dark_red),
+        "ΔE00 3.19, ΔE94 3.21, ΔE76 5.63"
+    );
+
+    // Colors that sRGB cannot show are compared before their chroma is
+    // reduced. These greens only differ in chroma, which sRGB cannot show.
+    let space = ColorSpace::DISPLAY_P3;
+    let green = |c| {
+        let lch = OkLCh {
+            l: 0.845,
+            c,
+            h: 145.0,
+        };
+        space.to_rgb(lch.to_oklab().to_xyz(), 0xffff)
+    };
+    let (first, second) = (green(0.36), green(0.32));
+    assert_eq!(
+        format_difference(
+            Lab::from_xyz(space.to_xyz(first)),
+            Lab::from_xyz(space.to_xyz(second))
+        ),
+        "ΔE00 3.48, ΔE94 3.44, ΔE76 23.91"
+    );
+    assert_eq!(
+        format_difference(
+            Lab::from_rgb(space.to_srgb(first)),
+            Lab::from_rgb(space.to_srgb(second))
+        ),
+        "ΔE00 0.00, ΔE94 0.00, ΔE76 0.00"
+    );
+}
+
+#[test]
+fn test_alpha() {
+    let opaque = ARGB::new(0xff, 0xff, 0x00, 0xff);
+    let translucent = ARGB::new(0x80, 0x12, 0x34, 0x56);
+    let compactable = ARGB::new(0x88, 0xaa, 0xbb, 0xcc);
+
+    let fmt: Format = "rgba".parse().unwrap();
+    assert_eq!(fmt.format(opaque.into()), "rgba(255, 0, 255, 1)");
+    assert_eq!(fmt.format(translucent.into()), "rgba(18, 52, 86, 0.502)");
+    assert_eq!(fmt.format(ARGB::TRANSPARENT.into()), "rgba(0, 0, 0, 0)");
+
+    let fmt: Format = "hexa".parse().unwrap();
+    assert_eq!(fmt.format(translucent.into()), "#12345680");
+    assert_eq!(fmt.format(compactable.into()), "#aabbcc88");
+
+    let fmt: Format = "HEXA!".parse().unwrap();
+    assert_eq!(fmt.format(translucent.into()), "#12345680");
+    assert_eq!(fmt.format(compactable.into()), "#ABC8");
+
+    let fmt: Format = "hexa!".parse().unwrap();
+    assert_eq!(
+        fmt.format(ARGB::new(0x80, 0xaa, 0xbb, 0xcc).into()),
+        "#aabbcc80"
+    );
+
+    let fmt: Format = "ahex".parse().unwrap();
+    assert_eq!(fmt.format(translucent.into()), "#80123456");
+
+    let fmt: Format = "AHEX".parse().unwrap();
+    assert_eq!(fmt.format(compactable.into()), "#88AABBCC");
+
+    let fmt: FormatString = "#%{02ha}%{02hr}%{02hg}%{02hb}".parse().unwrap();
+    assert_eq!(fmt.format(translucent.into()), "#80123456");
+}
+
+#[test]
+fn test_deep_color() {
+    // 10 bit gray 0x200 does not survive the reduction to 8 bits
+    let deep = ARGB16::new(0xffff, 0x8020, 0x8020, 0x8020);
+
+    let fmt: Format = "hex".parse().unwrap();
+    assert_eq!(fmt.format(deep), "#808080");
+
+    let fmt: Format = "lab".parse().unwrap();
+    assert_eq!(fmt.format(deep), "lab(53.44, 0.00, 0.00)");
+    assert_eq!(
+        fmt.format(ARGB::new(0xff, 0x80, 0x80, 0x80).into()),
+        "lab(53.59, 0.00, 0.00)"
+    );
+}
+
+#[test]
+fn test_wide_gamut() {
+    let red = ARGB::new(0xff, 0xff, 0, 0).into();
+    assert_eq!(
+        Format::DisplayP3.format(red),
+        "color(display-p3 0.9175 0.2003 0.1386)"
+    );
+    assert_eq!(
+        Format::Rec2020.format(red),
+        "color(rec2020 0.7920 0.2310 0.0738)"
+    );
+    assert_eq!(
+        Format::DisplayP3.format_in(red, &ColorSpace::DISPLAY_P3),
+        "color(display-p3 1.0000 0.0000 0.0000)"
+    );
+    assert_eq!(
+        Format::LowercaseHex(HexCompaction::Full).format_in(red, &ColorSpace::DISPLAY_P3),
+        "#ff3428"
+    );
+
+    let fmt: FormatString = "%{r} %{g} %{b}".parse().unwrap();
+    assert_eq!(fmt.format_in(red, &ColorSpace::DISPLAY_P3), "255 52 40");
+
+    let fmt: FormatString = "%{p3.r} %{p3.g} %{p3.b} %{rec2020.r}".parse().unwrap();
+    assert_eq!(fmt.format(red), "234 51 35 202");
+    assert_eq!(fmt.format_in(red, &ColorSpace::DISPLAY_P3), "255 0 0 221");
+}
+
+#[test]
+fn test_float_and_percentage() {
+    let color = ARGB::new(0x80, 0xff, 0x80, 0x00).into();
+    let format = |template: &str| template.parse::<FormatString>().unwrap().format(color);
+    assert_eq!(format("%{.3fg}"), "0.502");
+    assert_eq!(format("%{fr} %{fb} %{fg}"), "1 0 0.502");
+    assert_eq!(format("%{%g}"), "50%");
+    assert_eq!(format("%{.1%g}"), "50.2%");
+    assert_eq!(format("%{.2g}"), "128.00");
+    assert_eq!(format("%{.0fg}"), "1");
+    assert_eq!(format("%{ 7.2%g}"), " 50.20%");
+    assert_eq!(format("%{.2fhsl.h} %{%hsl.s}"), "0.08 100%");
+    assert_eq!(
+        format("vec3(%{.3fr}, %{.3fg}, %{.3fb})"),
+        "vec3(1.000, 0.502, 0.000)"
+    );
+    assert_eq!(
+        format("QColor::fromRgbF(%{fr}, %{fg}, %{fb}, %{fa})"),
+        "QColor::fromRgbF(1, 0.502, 0, 0.502)"
+    );
+    assert_eq!(
+        format("rgb(%{.1%r} %{.1%g} %{.1%b})"),
+        "rgb(100.0% 50.2% 0.0%)"
+    );
+}
+
+#[test]
+fn test_bit_depth() {
+    let color = ARGB16::new(0xffff, 0x1234, 0xabcd, 0x0000);
+    let format = |template: &str| template.parse::<FormatString>().unwrap().format(color);
+    assert_eq!(format("%{04hr:16}%{04hg:16}%{04hb:16}"), "1234abcd0000");
+    assert_eq!(format("%{02hr}"), "12");
+    assert_eq!(format("%{hr:4}%{hg:4}"), "1a");
+    assert_eq!(format("%{03hr:12}/%{03Hg:12}"), "123/ABC");
+    assert_eq!(format("%{a:10} %{b:1}"), "1023 0");
+    assert_eq!(format("%{hsl.h:8}"), "80");
+    assert_eq!(format("%{.3fg:16} %{%r:4}"), "0.671 7%");
+    assert_eq!(format("%{lab.l:8} %{cmyk.k:1}"), "156 0");
+
+    // Channels that are signed or unbounded cannot be scaled
+    for template in &[
+        "%{lab.a:8}",
+        "%{cct.duv:4}",
+        "%{cct.k:8}",
+        "%{x:16}",
+        "%{ansi256:4}",
+    ] {
+        assert!(template.parse::<FormatString>().is_err());
+    }
+
+    assert_eq!(Format::X11.format(color), "rgb:1234/abcd/0000");
+    assert_eq!(
+        Format::X11.format(ARGB::new(0xff, 0xff, 0, 0x80).into()),
+        "rgb:ffff/0000/8080"
+    );
+}
+
+#[test]
+fn test_context() {
+    let color = ARGB::new(0xff, 0xff, 0x00, 0xff).into();
+    let context = Context {
+        position: (123, 456),
+        screen: 1,
+        time: 1_700_000_000,
+    };
+    let format = |template: &str| {
+        let template: FormatString = template.parse().unwrap();
+        template.format_at(color, &ColorSpace::SRGB, &context)
+    };
+    assert_eq!(format("%{x},%{y} %{hex}"), "123,456 #ff00ff");
+    assert_eq!(
+        format("%{hex!} %{HEX!} %{HEXA} %{ahex}"),
+        "#f0f #F0F #FF00FFFF #ffff00ff"
+    );
+    assert_eq!(
+        format("%{05x} %{hy} %{screen} %{time}"),
+        "00123 1c8 1 1700000000"
+    );
+    assert_eq!(format("%{xyz.x} %{x}"), "59 123");
+    // The position channels are still usable as padding characters
+    assert_eq!(format("%{x4r} %{y4g} %{r3g}"), "x255 yyy0 rr0");
+    assert_eq!(
+        Format::RGB.format_at(color, &ColorSpace::SRGB, &context),
+        "rgb(255, 0, 255)"
+    );
+
+    let fmt: FormatString = "%{x} %{time}".parse().unwrap();
+    assert_eq!(fmt.format(color), "0 0");
+    assert!("%{hex!!}".parse::<FormatString>().is_err());
+    assert!("%{02hex}".parse::<FormatString>().is_err());
+}
+
+// The custom format equivalents listed in the README print exactly what the
+// built-in formats do
+#[test]
+fn test_custom_equivalents() {
+    let equivalents = [
+        ("hex", "#%{02hr}%{02hg}%{02hb}"),
+        ("HEX", "#%{02Hr}%{02Hg}%{02Hb}"),
+        ("hex!", "%{hex!}"),
+        ("HEX!", "%{HEX!}"),
+        ("hexa", "#%{02hr}%{02hg}%{02hb}%{02ha}"),
+        ("HEXA", "#%{02Hr}%{02Hg}%{02Hb}%{02Ha}"),
+        ("hexa!", "%{hexa!}"),
+        ("HEXA!", "%{HEXA!}"),
+        ("ahex", "#%{02ha}%{02hr}%{02hg}%{02hb}"),
+        ("AHEX", "#%{02Ha}%{02Hr}%{02Hg}%{02Hb}"),
+        ("rgb", "rgb(%{r}, %{g}, %{b})"),
+        ("rgba", "rgba(%{r}, %{g}, %{b}, %{fa})"),
+        ("plain", "%{r};%{g};%{b}"),
+        ("hsl", "hsl(%{hsl.h}, %{hsl.s}%%, %{hsl.l}%%)"),
+        ("hsv", "hsv(%{hsv.h}, %{hsv.s}%%, %{hsv.v}%%)"),
+        ("lab", "lab(%{.2lab.l}, %{.2lab.a}, %{.2lab.b})"),
+        ("lch", "lch(%{.2lch.l}, %{.2lch.c}, %{.2lch.h})"),
+        ("xyz", "xyz(%{.2xyz.x}, %{.2xyz.y}, %{.2xyz.z})"),
+        ("oklab", "oklab(%{.2oklab.l}%% %{.4foklab.a} %{.4foklab.b})"),
+        ("oklch", "oklch(%{.2oklch.l}%% %{.4foklch.c} %{.2oklch.h})"),
+        ("hwb", "hwb(%{hwb.h} %{hwb.w}%% %{hwb.b}%%)"),
+        (
+            "cmyk",
+            "cmyk(%{cmyk.c}%%, %{cmyk.m}%%, %{cmyk.y}%%, %{cmyk.k}%%)",
+        ),
+        ("name", "%{name}"),
+        (
+            "apca",
+            "Lc %{apca.white} on white, Lc %{apca.black} on black",
+        ),
+        (
+            "protanopia",
+            "#%{02hprotanopia.r}%{02hprotanopia.g}%{02hprotanopia.b}",
+        ),
+        (
+            "deuteranopia",
+            "#%{02hdeuteranopia.r}%{02hdeuteranopia.g}%{02hdeuteranopia.b}",
+        ),
+        (
+            "tritanopia",
+            "#%{02htritanopia.r}%{02htritanopia.g}%{02htritanopia.b}",
+        ),
+        (
+            "achromatopsia",
+            "#%{02hachromatopsia.r}%{02hachromatopsia.g}%{02hachromatopsia.b}",
+        ),
+        ("ansi16", "%{ansi16}"),
+        ("ansi256", "%{ansi256}"),
+        ("sgr16", "%{sgr16}"),
+        ("kelvin", "%{cct.k}K (Duv %{.4fcct.duv})"),
+        ("p3", "color(display-p3 %{.4fp3.r} %{.4fp3.g} %{.4fp3.b})"),
+        (
+            "rec2020",
+            "color(rec2020 %{.4frec2020.r} %{.4frec2020.g} %{.4frec2020.b})",
+        ),
+        ("sgr256", "38;5;%{ansi256}"),
+        ("sgr24", "38;2;%{r};%{g};%{b}"),
+        ("x11", "rgb:%{04hr:16}/%{04hg:16}/%{04hb:16}"),
+    ];
+    // A grid over the sRGB cube, and colors whose hues are just below 360
+    let levels = || (0..=0xffu8).step_by(5);
+    let grid = levels().flat_map(|r| levels().flat_map(move |g| levels().map(move |b| (r, g, b))));
+    let colors: Vec<ARGB> = grid
+        .chain(vec![
+            (0xff, 0, 1),
+            (0xff, 0, 2),
+            (0, 0x64, 0xa9),
+            (0, 0x05, 0x9a),
+        ])
+        .map(|(r, g, b)| ARGB::new(0x80 | r, r, g, b))
+        .collect();
+    // Searching for the nearest named color, palette index or temperature is
+    // slow, so these are only checked on part of the colors
+    let searches = ["name", "ansi16", "ansi256", "sgr16", "sgr256", "kelvin"];
+    for (name, template) in equivalents.iter() {
+        let format: Format = name.parse().unwrap();
+        let template: FormatString = template.parse().unwrap();
+        let step = if searches.contains(name) { 9 } else { 1 };
+        for color in colors.iter().step_by(step) {
+            let color = ARGB16::from(*color);
+            assert_eq!(
+                format.format(color),
+                template.format(color),
+                "{} {:?}",
+                name,
+                color
+            );
+        }
+    }
 }
@@ -0,0 +1,297 @@
+use anyhow::{anyhow, Result};
+use xcb::base as xbase;
+use xcb::base::Connection;
+use xcb::xproto;
+
+use crate::atoms;
+#[cfg(test)]
+use crate::color::ARGB16;
+use crate::color::{ColorSpace, Transfer, XYZ};
+#[cfg(test)]
+use crate::format::{Format, FormatColor};
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| anyhow!("Truncated ICC profile"))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or_else(|| anyhow!("Truncated ICC profile"))
+}
+
+fn read_s15_fixed16(data: &[u8], offset: usize) -> Result<f32> {
+    Ok(read_u32(data, offset)? as i32 as f32 / 65536.0)
+}
+
+fn signature(data: &[u8], offset: usize) -> Result<&[u8]> {
+    data.get(offset..offset + 4)
+        .ok_or_else(|| anyhow!("Truncated ICC profile"))
+}
+
+// Finds the data of a tag from the tag table
+fn tag<'a>(profile: &'a [u8], name: &[u8]) -> Result<&'a [u8]> {
+    let count = read_u32(profile, 128)? as usize;
+    for entry in (0..count).map(|n| 132 + n * 12) {
+        if signature(profile, entry)? == name {
+            let offset = read_u32(profile, entry + 4)? as usize;
+            let size = read_u32(profile, entry + 8)? as usize;
+            return profile
+                .get(offset..offset + size)
+                .ok_or_else(|| anyhow!("Truncated ICC profile"));
+        }
+    }
+    Err(anyhow!(
+        "ICC profile has no {} tag",
+        String::from_utf8_lossy(name)
+    ))
+}
+
+fn parse_xyz(data: &[u8]) -> Result<XYZ> {
+    if signature(data, 0)? != b"XYZ " {
+        return Err(anyhow!("Unsupported ICC colorant type"));
+    }
+    let [x, y, z] = [
+        read_s15_fixed16(data, 8)?,
+        read_s15_fixed16(data, 12)?,
+        read_s15_fixed16(data, 16)?,
+    ];
+    Ok(XYZ { x, y, z })
+}
+
+fn parse_curve(data: &[u8]) -> Result<Transfer> {
+    match signature(data, 0)? {
+        b"curv" => {
+            let count = read_u32(data, 8)? as usize;
+            match count {
+                0 => Ok(Transfer::Parametric([1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0])),
+                1 => {
+                    let gamma = f32::from(read_u16(data, 12)?) / 256.0;
+                    Ok(Transfer::Parametric([gamma, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0]))
+                }
+                _ => (0..count)
+                    .map(|n| Ok(f32::from(read_u16(data, 12 + n * 2)?) / 65535.0))
+                    .collect::<Result<Vec<_>>>()
+                    .map(Transfer::Table),
+            }
+        }
+        b"para" => {
+            let kind = read_u16(data, 8)?;
+            let count = match kind {
+                0 => 1,
+                1 => 3,
+                2 => 4,
+                3 => 5,
+                4 => 7,
+                _ => return Err(anyhow!("Unsupported ICC parametric curve")),
+            };
+            let mut p = [0.0; 7];
+            for (n, parameter) in p.iter_mut().take(count).enumerate() {
+                *parameter = read_s15_fixed16(data, 12 + n * 4)?;
+            }
+            // Expand the simpler curves into the general form of the function
+            let [g, a, b, c, d, e, f] = p;
+            if (kind == 1 || kind == 2) && a == 0.0 {
+                return Err(anyhow!("Invalid ICC parametric curve"));
+            }
+            Ok(Transfer::Parametric(match kind {
+                0 => [g, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+                1 => [g, a, b, 0.0, -b / a, 0.0, 0.0],
+                2 => [g, a, b, 0.0, -b / a, c, c],
+                _ => [g, a, b, c, d, e, f],
+            }))
+        }
+        _ => Err(anyhow!("Unsupported ICC curve type")),
+    }
+}
+
+/// Reads the color space of an RGB display profile made of colorant and tone
+/// curve tags. Colors are adapted so that the white of the display becomes
+/// the D65 white of sRGB.
+pub fn parse_profile(profile: &[u8]) -> Result<ColorSpace> {
+    if profile.len() < 132 || signature(profile, 36)? != b"acsp" {
+        return Err(anyhow!("Invalid ICC profile"));
+    }
+    if signature(profile, 16)? != b"RGB " || signature(profile, 20)? != b"XYZ " {
+        return Err(anyhow!("Unsupported ICC profile, expected an RGB display"));
+    }
+
+    // Colorants are relative to the D50 white of the profile connection space
+    let colorant = |name: &[u8]| parse_xyz(tag(profile, name)?).map(XYZ::from_d50);
+    let curve = |name: &[u8]| parse_curve(tag(profile, name)?);
+
+    ColorSpace::new(
+        [colorant(b"rXYZ")?, colorant(b"gXYZ")?, colorant(b"bXYZ")?],
+        [curve(b"rTRC")?, curve(b"gTRC")?, curve(b"bTRC")?],
+    )
+}
+
+/// Reads the profile that color management tools publish on the root window
+/// following the X Color Management specification. Returns `None` if the
+/// display has no profile.
+pub fn display_profile(conn: &Connection, root: xproto::Window) -> Result<Option<ColorSpace>> {
+    let atom = atoms::get(conn, "_ICC_PROFILE")?;
+    if atom == xbase::NONE {
+        return Ok(None);
+    }
+
+    let reply = xproto::get_property(conn, false, root, atom, xproto::ATOM_ANY, 0, u32::MAX / 4)
+        .get_reply()?;
+    if reply.format() != 8 || reply.value_len() == 0 {
+        return Ok(None);
+    }
+
+    parse_profile(reply.value::<u8>()).map(Some)
+}
+
+#[cfg(test)]
+fn build_profile(space: &[u8; 4], tags: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+    let mut header = vec![0; 128];
+    header[16..20].copy_from_slice(space);
+    header[20..24].copy_from_slice(b"XYZ ");
+    header[36..40].copy_from_slice(b"acsp");
+
+    let mut table = (tags.len() as u32).to_be_bytes().to_vec();
+    let mut data = Vec::new();
+    let mut offset = 128 + 4 + tags.len() * 12;
+    for (name, tag) in tags {
+        table.extend_from_slice(*name);
+        table.extend_from_slice(&(offset as u32).to_be_bytes());
+        table.extend_from_slice(&(tag.len() as u32).to_be_bytes());
+        data.extend_from_slice(tag);
+        offset += tag.len();
+    }
+    [header, table, data].concat()
+}
+
+#[cfg(test)]
+fn fixed_tag(kind: &[u8; 4], prefix: &[u8], values: &[f32]) -> Vec<u8> {
+    let mut tag = kind.to_vec();
+    tag.extend_from_slice(&[0; 4]);
+    tag.extend_from_slice(prefix);
+    for value in values {
+        tag.extend_from_slice(&((value * 65536.0).round() as i32).to_be_bytes());
+    }
+    tag
+}
+
+#[cfg(test)]
+fn srgb_profile(trc: Vec<u8>) -> Vec<u8> {
+    // The colorants of sRGB adapted to D50
+    build_profile(
+        b"RGB ",
+        &[
+            (
+                b"rXYZ",
+                fixed_tag(b"XYZ ", &[], &[0.436_074_7, 0.222_504_5, 0.013_932_2]),
+            ),
+            (
+                b"gXYZ",
+                fixed_tag(b"XYZ ", &[], &[0.385_064_9, 0.716_878_6, 0.097_104_5]),
+            ),
+            (
+                b"bXYZ",
+                fixed_tag(b"XYZ ", &[], &[0.143_080_4, 0.060_616_9, 0.714_173_3]),
+            ),
+            (b"rTRC", trc.clone()),
+            (b"gTRC", trc.clone()),
+            (b"bTRC", trc),
+        ],
+    )
+}
+
+#[test]
+fn test_parse_profile() {
+    let srgb_trc = fixed_tag(
+        b"para",
+        &[0, 3, 0, 0],
+        &[2.4, 1.0 / 1.055, 0.055 / 1.055, 1.0 / 12.92, 0.04045],
+    );
+    let space = parse_profile(&srgb_profile(srgb_trc)).unwrap();
+
+    let colors = [
+        ARGB16::new(0xffff, 0xffff, 0xffff, 0xffff),
+        ARGB16::new(0xffff, 0xffff, 0, 0),
+        ARGB16::new(0xffff, 0x1e1e, 0x9090, 0xffff),
+        ARGB16::new(0xffff, 0x0808, 0x0404, 0x0202),
+    ];
+    for color in colors.iter() {
+        let (actual, expected) = (space.to_xyz(*color), ColorSpace::SRGB.to_xyz(*color));
+        assert!((actual.x - expected.x).abs() < 2e-3, "{:?}", color);
+        assert!((actual.y - expected.y).abs() < 2e-3, "{:?}", color);
+        assert!((actual.z - expected.z).abs() < 2e-3, "{:?}", color);
+
+        let converted = space.to_srgb(*color);
+        for (a, b) in [
+            (converted.r, color.r),
+            (converted.g, color.g),
+            (converted.b, color.b),
+        ] {
+            assert!((i32::from(a) - i32::from(b)).abs() < 0x100, "{:?}", color);
+        }
+    }
+}
+
+#[test]
+fn test_parse_curves() {
+    let gray = ARGB16::new(0xffff, 0x8000, 0x8000, 0x8000);
+    let luminance = |trc: Vec<u8>| parse_profile(&srgb_profile(trc)).unwrap().to_xyz(gray).y;
+
+    let gamma = fixed_tag(b"curv", &[0, 0, 0, 1, 0x02, 0x33], &[]);
+    assert!((luminance(gamma) - 0.5f32.powf(2.2)).abs() < 1e-3);
+
+    let linear = fixed_tag(b"curv", &[0, 0, 0, 0], &[]);
+    assert!((luminance(linear) - 0.5).abs() < 1e-3);
+
+    let table = fixed_tag(b"curv", &[0, 0, 0, 3, 0, 0, 0x40, 0, 0xff, 0xff], &[]);
+    assert!((luminance(table) - 0.25).abs() < 1e-3);
+
+    let para = fixed_tag(b"para", &[0, 0, 0, 0], &[2.0]);
+    assert!((luminance(para) - 0.25).abs() < 1e-3);
+
+    let offset = fixed_tag(b"para", &[0, 2, 0, 0], &[1.0, 1.0, 0.0, 0.1]);
+    assert!((luminance(offset) - 0.6).abs() < 1e-3);
+}
+
+#[test]
+fn test_profile_temperature() {
+    let linear = fixed_tag(b"curv", &[0, 0, 0, 0], &[]);
+    let space = parse_profile(&srgb_profile(linear)).unwrap();
+
+    let warm = ARGB16::new(0xffff, 0xffff, 0xc000, 0x8000);
+    assert_eq!(Format::Kelvin.format_in(warm, &space), "4536K (Duv 0.0019)");
+    assert_eq!(Format::Kelvin.format(warm), "3261K (Duv -0.0010)");
+}
+
+#[test]
+fn test_invalid_profile() {
+    let trc = fixed_tag(b"curv", &[0, 0, 0, 0], &[]);
+    assert!(parse_profile(&[0; 64]).is_err());
+    assert!(parse_profile(&build_profile(b"RGB ", &[])).is_err());
+    assert!(parse_profile(&build_profile(b"CMYK", &[])).is_err());
+
+    let mut truncated = srgb_profile(trc.clone());
+    truncated.truncate(200);
+    assert!(parse_profile(&truncated).is_err());
+
+    let unsupported = fixed_tag(b"para", &[0, 9, 0, 0], &[]);
+    assert!(parse_profile(&srgb_profile(unsupported)).is_err());
+
+    let flat = fixed_tag(b"para", &[0, 1, 0, 0], &[2.2, 0.0, 0.5]);
+    assert!(parse_profile(&srgb_profile(flat)).is_err());
+
+    let collapsed = build_profile(
+        b"RGB ",
+        &[
+            (b"rXYZ", fixed_tag(b"XYZ ", &[], &[0.5, 0.5, 0.5])),
+            (b"gXYZ", fixed_tag(b"XYZ ", &[], &[0.5, 0.5, 0.5])),
+            (b"bXYZ", fixed_tag(b"XYZ ", &[], &[0.1, 0.2, 0.3])),
+            (b"rTRC", trc.clone()),
+            (b"gTRC", trc.clone()),
+            (b"bTRC", trc),
+        ],
+    );
+    assert!(parse_profile(&collapsed).is_err());
+}
@@ -4,7 +4,7 @@ use xcb::base as xbase;
 use xcb::base::Connection;
 use xcb::xproto;
 
-use crate::color::{self, ARGB};
+use crate::color::{self, Deficiency, ARGB, ARGB16};
 use crate::draw::draw_magnifying_glass;
 use crate::pixel::PixelSquare;
 use crate::util::EnsureOdd;
@@ -51,7 +51,7 @@ fn create_new_xcursor(
     preview_width: u32,
 ) -> Result<u32> {
     Ok(unsafe {
-        let mut cursor_image = XcursorImageCreate(preview_width as i32, preview_width as i32);
+        let cursor_image = XcursorImageCreate(preview_width as i32, preview_width as i32);
 
         // set the "hot spot" - this is where the pointer actually is inside the image
         (*cursor_image).xhot = preview_width / 2;
@@ -66,7 +66,7 @@ fn create_new_xcursor(
         // cursor and the screenshot (to account for integer division so no out of bounds accesses
         // occur when upscaling the image in `draw_magnifying_glass`)
         let mut pixel_size = cursor_pixels.width() / screenshot_pixels.width();
-        if pixel_size % 2 == 0 {
+        if pixel_size.is_multiple_of(2) {
             pixel_size += 1;
         } else {
             pixel_size += 2;
@@ -94,6 +94,7 @@ fn get_window_rect_around_pointer(
     (pointer_x, pointer_y): (i16, i16),
     preview_width: u32,
     scale: u32,
+    filter: Option<Deficiency>,
 ) -> Result<(u16, Vec<ARGB>)> {
     let root = screen.root();
     let root_width = screen.width_in_pixels() as isize;
@@ -123,7 +124,13 @@ fn get_window_rect_around_pointer(
 
     // grab a screenshot of the rect
     let rect = (x as i16, y as i16, size_x as u16, size_y as u16);
-    let screenshot_rect = color::window_rect(conn, root, rect)?;
+    let screenshot_rect: Vec<ARGB> = color::window_rect(conn, root, rect)?
+        .into_iter()
+        .map(|color| match filter {
+            Some(deficiency) => ARGB::from(deficiency.simulate(color)),
+            None => ARGB::from(color),
+        })
+        .collect();
 
     // the entire portion of the screenshot is on screen
     if size_x == size && size_y == size {
@@ -150,6 +157,7 @@ fn create_new_cursor(
     screen: &xproto::Screen,
     preview_width: u32,
     scale: u32,
+    filter: Option<Deficiency>,
     point: Option<(i16, i16)>,
 ) -> Result<u32> {
     let point = match point {
@@ -161,22 +169,55 @@ fn create_new_cursor(
         }
     };
 
-    let (w, p) = get_window_rect_around_pointer(conn, screen, point, preview_width, scale)?;
+    let (w, p) = get_window_rect_around_pointer(conn, screen, point, preview_width, scale, filter)?;
     let pixels = PixelSquare::new(&p[..], w.into());
     create_new_xcursor(conn, &pixels, preview_width)
 }
 
+// Finds the deepest window containing the given root window coordinates and translates the
+// coordinates into that window's coordinate space
+fn window_at(
+    conn: &Connection,
+    root: xproto::Window,
+    (x, y): (i16, i16),
+) -> Result<(xproto::Window, (i16, i16))> {
+    let mut window = root;
+    loop {
+        let reply = xproto::translate_coordinates(conn, root, window, x, y).get_reply()?;
+        if reply.child() == xbase::NONE {
+            return Ok((window, (reply.dst_x(), reply.dst_y())));
+        }
+        window = reply.child();
+    }
+}
+
+// Reads the color of a single pixel. Windows with an alpha channel are sampled directly since the
+// root window only contains their composited, opaque result.
+fn pick_color(conn: &Connection, root: xproto::Window, point: (i16, i16)) -> Result<ARGB16> {
+    let (window, (x, y)) = window_at(conn, root, point)?;
+    let has_alpha = window != root && xproto::get_geometry(conn, window).get_reply()?.depth() == 32;
+
+    let pixels = if has_alpha {
+        color::window_rect(conn, window, (x, y, 1, 1))?
+    } else {
+        color::window_rect(conn, root, (point.0, point.1, 1, 1))?
+    };
+
+    Ok(pixels[0])
+}
+
 pub fn wait_for_location(
     conn: &Connection,
     screen: &xproto::Screen,
     preview_width: u32,
     scale: u32,
-) -> Result<Option<ARGB>> {
+    filter: Option<Deficiency>,
+) -> Result<Option<(ARGB16, (i16, i16))>> {
     let root = screen.root();
     let preview_width = preview_width.ensure_odd();
 
     // grab the cursor to listen to all of its events
-    let mut cursor = create_new_cursor(conn, screen, preview_width, scale, None)?;
+    let mut cursor = create_new_cursor(conn, screen, preview_width, scale, filter, None)?;
     grab_pointer(conn, root, cursor)?;
 
     let result = loop {
@@ -187,13 +228,8 @@ pub fn wait_for_location(
                     let event: &xproto::ButtonPressEvent = unsafe { xbase::cast_event(&event) };
                     match event.detail() {
                         SELECTION_BUTTON => {
-                            let pixels = color::window_rect(
-                                conn,
-                                root,
-                                (event.root_x(), event.root_y(), 1, 1),
-                            )?;
-
-                            break Some(pixels[0]);
+                            let point = (event.root_x(), event.root_y());
+                            break Some((pick_color(conn, root, point)?, point));
                         }
                         RIGHT_BUTTON => {
                             return Ok(None);
@@ -208,6 +244,7 @@ pub fn wait_for_location(
                         screen,
                         preview_width,
                         scale,
+                        filter,
                         Some((event.root_x(), event.root_y())),
                     )?;
                     update_cursor(conn, new_cursor)?;
@@ -1,12 +1,22 @@
+#![allow(clippy::upper_case_acronyms)]
+
+mod ansi;
 mod atoms;
 mod cli;
 mod color;
 mod draw;
 mod format;
+mod icc;
 mod location;
+mod names;
+mod palette;
 mod pixel;
 mod selection;
 mod util;
+mod visual;
+
+use std::iter;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use anyhow::{anyhow, Result};
 use clap::{value_t, ArgMatches, ErrorKind};
@@ -14,8 +24,10 @@ use nix::unistd::ForkResult;
 use xcb::base::Connection;
 
 use crate::cli::get_cli;
-use crate::format::{Format, FormatColor, FormatString};
+use crate::color::{ColorSpace, Deficiency, InkSeparation, Lab, OkLCh, OkLab};
+use crate::format::{format_difference, Context, Format, FormatColor, FormatString};
 use crate::location::wait_for_location;
+use crate::palette::Palette;
 use crate::selection::{into_daemon, set_selection, Selection};
 
 const DEFAULT_PREVIEW_SIZE: u32 = 256 - 1;
@@ -26,19 +38,38 @@ fn run(args: &ArgMatches) -> Result<()> {
         clap::Error::with_description(message, clap::ErrorKind::InvalidValue).exit()
     }
 
+    fn percentage(args: &ArgMatches, name: &str) -> f32 {
+        match value_t!(args.value_of(name), f32) {
+            Ok(value) if (0.0..=100.0).contains(&value) => value / 100.0,
+            Ok(_) => error(&format!(
+                "--{} must be between 0 and 100",
+                name.replace('_', "-")
+            )),
+            Err(e) if e.kind == ErrorKind::ArgumentNotFound => 1.0,
+            Err(e) => error(&format!("{}", e)),
+        }
+    }
+
+    let separation = InkSeparation {
+        black_generation: percentage(args, "black_generation"),
+        under_color_removal: percentage(args, "under_color_removal"),
+    };
+
     let custom_format;
     let simple_format;
     let formatter: &dyn FormatColor = if let Some(custom) = args.value_of("custom") {
         custom_format = custom
             .parse::<FormatString>()
-            .unwrap_or_else(|_| error("Invalid format string"));
+            .unwrap_or_else(|_| error("Invalid format string"))
+            .with_ink_separation(separation);
         &custom_format
     } else {
         simple_format = args
             .value_of("format")
             .unwrap_or("hex")
             .parse::<Format>()
-            .unwrap_or_else(|e| error(&format!("{}", e)));
+            .unwrap_or_else(|e| error(&format!("{}", e)))
+            .with_ink_separation(separation);
         &simple_format
     };
 
@@ -52,6 +83,25 @@ fn run(args: &ArgMatches) -> Result<()> {
             _ => error(&format!("{}", e)),
         });
 
+    let filter = args.value_of("simulate").map(|name| {
+        name.parse::<Deficiency>()
+            .unwrap_or_else(|e| error(&format!("{}", e)))
+    });
+
+    // The ICC profile is read from the display once connected
+    let use_profile = args.value_of("display_space") == Some("icc");
+    let space = match args.value_of("display_space") {
+        Some("icc") | None => ColorSpace::SRGB,
+        Some(name) => name
+            .parse::<ColorSpace>()
+            .unwrap_or_else(|e| error(&format!("{}", e))),
+    };
+
+    let palette = args.value_of("palette").map(|name| {
+        name.parse::<Palette>()
+            .unwrap_or_else(|e| error(&format!("{}", e)))
+    });
+
     let selection = args.values_of("selection").and_then(|mut v| {
         v.next()
             .map_or(Some(Selection::Clipboard), |v| v.parse::<Selection>().ok())
@@ -60,22 +110,75 @@ fn run(args: &ArgMatches) -> Result<()> {
     let background = std::env::var("XCOLOR_FOREGROUND").is_err();
 
     let print_position = args.is_present("position");
+    let compare = args.is_present("compare");
 
     let mut in_parent = true;
 
-    let (conn, screen) = Connection::connect_with_xlib_display()?;
+    let (conn, screen_num) = Connection::connect_with_xlib_display()?;
 
     {
         let screen = conn
             .get_setup()
             .roots()
-            .nth(screen as usize)
+            .nth(screen_num as usize)
             .ok_or_else(|| anyhow!("Could not find screen"))?;
         let root = screen.root();
 
-        if let Some(color) = wait_for_location(&conn, &screen, preview_size, scale)? {
-            let output = formatter.format(color);
+        let space = if use_profile {
+            icc::display_profile(&conn, root)?
+                .ok_or_else(|| anyhow!("Display has no ICC profile"))?
+        } else {
+            space
+        };
+
+        let pick = || -> Result<_> {
+            let picked = wait_for_location(&conn, &screen, preview_size, scale, filter)?;
+            let time = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            Ok(picked.map(|(color, position)| {
+                let context = Context {
+                    position,
+                    screen: screen_num,
+                    time,
+                };
+                (color, context)
+            }))
+        };
+        let output = match pick()? {
+            Some((first, first_context)) if compare => pick()?.map(|(second, second_context)| {
+                format!(
+                    "{}\n{}\n{}",
+                    formatter.format_at(first, &space, &first_context),
+                    formatter.format_at(second, &space, &second_context),
+                    format_difference(
+                        Lab::from_xyz(space.to_xyz(first)),
+                        Lab::from_xyz(space.to_xyz(second))
+                    )
+                )
+            }),
+            Some((color, context)) => Some(match palette {
+                // Generated colors are kept in Rec. 2020, the widest supported
+                // space, so that they are only mapped into the gamut of the
+                // output format when printed
+                Some(palette) => {
+                    let generated = palette
+                        .generate(OkLCh::from_oklab(OkLab::from_xyz(space.to_xyz(color))))
+                        .into_iter()
+                        .skip(1)
+                        .map(|lch| {
+                            let wide = ColorSpace::REC2020.to_rgb(lch.to_oklab().to_xyz(), color.a);
+                            formatter.format_at(wide, &ColorSpace::REC2020, &context)
+                        });
+                    iter::once(formatter.format_at(color, &space, &context))
+                        .chain(generated)
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                }
+                None => formatter.format_at(color, &space, &context),
+            }),
+            None => None,
+        };
 
+        if let Some(output) = output {
             if use_selection {
                 if background {
                     in_parent = match into_daemon()? {
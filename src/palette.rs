@@ -0,0 +1,154 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, Error, Result};
+
+#[cfg(test)]
+use crate::color::{ColorSpace, ARGB16};
+use crate::color::{OkLCh, OkLab, ARGB};
+
+// Number of colors in tint and shade ramps, including the picked color
+const RAMP_LENGTH: usize = 5;
+
+/// Palettes that can be generated from a picked color. The picked color is
+/// always the first color of the palette. Colors are generated in OKLCH and
+/// may be outside of the gamut of any display.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Palette {
+    Complementary,
+    Analogous,
+    Triadic,
+    SplitComplementary,
+    Tints,
+    Shades,
+}
+
+impl FromStr for Palette {
+    type Err = Error;
+
+    fn from_str(string: &str) -> Result<Palette, Self::Err> {
+        match string {
+            "complementary" => Ok(Palette::Complementary),
+            "analogous" => Ok(Palette::Analogous),
+            "triadic" => Ok(Palette::Triadic),
+            "split-complementary" => Ok(Palette::SplitComplementary),
+            "tints" => Ok(Palette::Tints),
+            "shades" => Ok(Palette::Shades),
+            _ => Err(anyhow!("Invalid palette")),
+        }
+    }
+}
+
+// Harmonies rotate the hue in OKLCH so that the colors keep their perceived
+// lightness and chroma
+fn rotate(color: OkLCh, degrees: &[f32]) -> Vec<OkLCh> {
+    let rotated = degrees.iter().map(|degrees| OkLCh {
+        h: (color.h + degrees).rem_euclid(360.0),
+        ..color
+    });
+    iter_with_first(color, rotated)
+}
+
+// Ramps mix the color with white or black in OKLab so that the steps are
+// perceptually even
+fn ramp(color: OkLCh, target: OkLab) -> Vec<OkLCh> {
+    let steps = (1..RAMP_LENGTH).map(|n| {
+        let amount = n as f32 / RAMP_LENGTH as f32;
+        OkLCh::from_oklab(color.to_oklab().interpolate(target, amount))
+    });
+    iter_with_first(color, steps)
+}
+
+fn iter_with_first(first: OkLCh, rest: impl Iterator<Item = OkLCh>) -> Vec<OkLCh> {
+    std::iter::once(first).chain(rest).collect()
+}
+
+impl Palette {
+    pub fn generate(self, color: OkLCh) -> Vec<OkLCh> {
+        match self {
+            Palette::Complementary => rotate(color, &[180.0]),
+            Palette::Analogous => rotate(color, &[-30.0, 30.0]),
+            Palette::Triadic => rotate(color, &[120.0, 240.0]),
+            Palette::SplitComplementary => rotate(color, &[150.0, 210.0]),
+            Palette::Tints => ramp(color, OkLab::from_rgb(ARGB::WHITE)),
+            Palette::Shades => ramp(color, OkLab::from_rgb(ARGB::BLACK)),
+        }
+    }
+}
+
+#[test]
+fn test_harmonies() {
+    let lch = OkLCh::from_rgb(ARGB16::new(0xffff, 0x6a6a, 0x8a8a, 0x7a7a));
+
+    let cases = [
+        (Palette::Complementary, vec![0.0, 180.0]),
+        (Palette::Analogous, vec![0.0, -30.0, 30.0]),
+        (Palette::Triadic, vec![0.0, 120.0, 240.0]),
+        (Palette::SplitComplementary, vec![0.0, 150.0, 210.0]),
+    ];
+    for (palette, rotations) in cases.iter() {
+        let colors = palette.generate(lch);
+        assert_eq!(colors.len(), rotations.len());
+        assert_eq!(colors[0], lch);
+        for (generated, rotation) in colors.iter().zip(rotations) {
+            let generated = OkLCh::from_rgb(generated.to_rgb());
+            let hue = (lch.h + rotation).rem_euclid(360.0);
+            assert!((generated.l - lch.l).abs() < 1e-3);
+            assert!((generated.c - lch.c).abs() < 1e-3);
+            assert!((generated.h - hue).abs() < 0.5);
+        }
+    }
+}
+
+#[test]
+fn test_ramps() {
+    let color = OkLCh::from_rgb(ARGB16::new(0xffff, 0x3333, 0x6666, 0x9999));
+
+    let tints = Palette::Tints.generate(color);
+    assert_eq!(tints.len(), RAMP_LENGTH);
+    assert_eq!(tints[0], color);
+    let tints: Vec<_> = tints.iter().map(|tint| tint.to_rgb()).collect();
+    assert!(tints.windows(2).all(|pair| pair[0].r < pair[1].r));
+
+    let shades = Palette::Shades.generate(color);
+    assert_eq!(shades.len(), RAMP_LENGTH);
+    assert_eq!(shades[0], color);
+    let shades: Vec<_> = shades.iter().map(|shade| shade.to_rgb()).collect();
+    assert!(shades.windows(2).all(|pair| pair[0].b > pair[1].b));
+
+    // Ramps are even in OKLab lightness
+    let steps: Vec<f32> = Palette::Tints
+        .generate(color)
+        .iter()
+        .map(|tint| tint.l)
+        .collect();
+    let step = (1.0 - color.l) / RAMP_LENGTH as f32;
+    assert!(steps
+        .windows(2)
+        .all(|pair| (pair[1] - pair[0] - step).abs() < 1e-4));
+}
+
+#[test]
+fn test_wide_gamut() {
+    // Pure Display P3 green is far outside of sRGB. Its palette keeps the
+    // chroma of the pick, so that it can be printed as Display P3.
+    let green = ARGB16::new(0xffff, 0, 0xffff, 0);
+    let color = OkLCh::from_oklab(OkLab::from_xyz(ColorSpace::DISPLAY_P3.to_xyz(green)));
+    let srgb = OkLCh::from_rgb(ARGB16::from(ARGB::new(0xff, 0, 0xff, 0)));
+    assert!(color.c > srgb.c + 0.05);
+
+    let colors = Palette::Analogous.generate(color);
+    assert!(colors
+        .iter()
+        .all(|generated| (generated.c - color.c).abs() < 1e-6));
+    let first = ColorSpace::DISPLAY_P3.to_rgb(colors[0].to_oklab().to_xyz(), 0xffff);
+    assert!(first.r < 0x0100 && first.g > 0xff00 && first.b < 0x0100);
+}
+
+#[test]
+fn test_parse_palette() {
+    assert_eq!(
+        "split-complementary".parse::<Palette>().unwrap(),
+        Palette::SplitComplementary
+    );
+    assert!("tetradic".parse::<Palette>().is_err());
+}
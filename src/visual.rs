@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use xcb::base::Connection;
+use xcb::xproto;
+
+use crate::color::ARGB16;
+
+/// Describes how the pixels of a ZPixmap image are laid out in memory. This is
+/// derived from the visual of the sampled window and the server setup. Pixels of
+/// indexed and DirectColor visuals are colormap entries rather than colors.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PixelFormat {
+    bits_per_pixel: u8,
+    big_endian: bool,
+    indexed: bool,
+    red_mask: u32,
+    green_mask: u32,
+    blue_mask: u32,
+    alpha_mask: u32,
+}
+
+fn find_visual(conn: &Connection, id: xproto::Visualid) -> Option<xproto::Visualtype> {
+    conn.get_setup()
+        .roots()
+        .flat_map(|screen| screen.allowed_depths())
+        .flat_map(|depth| depth.visuals())
+        .find(|visual| visual.visual_id() == id)
+}
+
+impl PixelFormat {
+    pub fn new(conn: &Connection, visual: xproto::Visualid, depth: u8) -> Result<PixelFormat> {
+        let setup = conn.get_setup();
+        let visual = find_visual(conn, visual).ok_or_else(|| anyhow!("Could not find visual"))?;
+
+        let bits_per_pixel = setup
+            .pixmap_formats()
+            .find(|format| format.depth() == depth)
+            .map(|format| format.bits_per_pixel())
+            .ok_or_else(|| anyhow!("Unsupported color depth"))?;
+        let big_endian = u32::from(setup.image_byte_order()) == xproto::IMAGE_ORDER_MSB_FIRST;
+
+        match u32::from(visual.class()) {
+            xproto::VISUAL_CLASS_TRUE_COLOR => PixelFormat::from_masks(
+                depth,
+                bits_per_pixel,
+                big_endian,
+                (visual.red_mask(), visual.green_mask(), visual.blue_mask()),
+            ),
+            xproto::VISUAL_CLASS_DIRECT_COLOR => PixelFormat::from_direct(
+                depth,
+                bits_per_pixel,
+                big_endian,
+                (visual.red_mask(), visual.green_mask(), visual.blue_mask()),
+            ),
+            xproto::VISUAL_CLASS_PSEUDO_COLOR
+            | xproto::VISUAL_CLASS_STATIC_COLOR
+            | xproto::VISUAL_CLASS_GRAY_SCALE
+            | xproto::VISUAL_CLASS_STATIC_GRAY => {
+                PixelFormat::from_indexed(bits_per_pixel, big_endian)
+            }
+            _ => Err(anyhow!("Unsupported visual class")),
+        }
+    }
+
+    fn from_indexed(bits_per_pixel: u8, big_endian: bool) -> Result<PixelFormat> {
+        let mut format = PixelFormat::from_masks(0, bits_per_pixel, big_endian, (0, 0, 0))?;
+        format.indexed = true;
+        Ok(format)
+    }
+
+    // Each channel of a DirectColor pixel indexes a colormap of its own. The
+    // server decomposes the pixel when its colors are queried.
+    fn from_direct(
+        depth: u8,
+        bits_per_pixel: u8,
+        big_endian: bool,
+        masks: (u32, u32, u32),
+    ) -> Result<PixelFormat> {
+        let mut format = PixelFormat::from_masks(depth, bits_per_pixel, big_endian, masks)?;
+        format.indexed = true;
+        Ok(format)
+    }
+
+    fn from_masks(
+        depth: u8,
+        bits_per_pixel: u8,
+        big_endian: bool,
+        (red_mask, green_mask, blue_mask): (u32, u32, u32),
+    ) -> Result<PixelFormat> {
+        if bits_per_pixel == 0 || !bits_per_pixel.is_multiple_of(8) || bits_per_pixel > 32 {
+            return Err(anyhow!("Unsupported pixel size"));
+        }
+
+        // Bits of the pixel that do not belong to any color channel hold alpha
+        let depth_mask = u32::MAX.checked_shr(32 - u32::from(depth)).unwrap_or(0);
+        let alpha_mask = depth_mask & !(red_mask | green_mask | blue_mask);
+
+        Ok(PixelFormat {
+            bits_per_pixel,
+            big_endian,
+            indexed: false,
+            red_mask,
+            green_mask,
+            blue_mask,
+            alpha_mask,
+        })
+    }
+
+    pub fn bytes_per_pixel(&self) -> usize {
+        usize::from(self.bits_per_pixel / 8)
+    }
+
+    /// Whether pixels have to be looked up from a colormap with `query_colors`
+    pub fn is_indexed(&self) -> bool {
+        self.indexed
+    }
+
+    /// The colormap entry that a pixel of an indexed visual refers to. Bits
+    /// outside the channels of DirectColor visuals are dropped.
+    pub fn colormap_index(&self, pixel: u32) -> u32 {
+        match self.red_mask | self.green_mask | self.blue_mask {
+            0 => pixel,
+            mask => pixel & mask,
+        }
+    }
+
+    /// Assembles the raw value of a pixel from its bytes
+    pub fn read(&self, bytes: &[u8]) -> u32 {
+        let assemble = |pixel: u32, &byte: &u8| pixel << 8 | u32::from(byte);
+        if self.big_endian {
+            bytes.iter().fold(0, assemble)
+        } else {
+            bytes.iter().rev().fold(0, assemble)
+        }
+    }
+
+    /// Extracts the color of a pixel of a visual with channel masks
+    pub fn decode(&self, pixel: u32) -> ARGB16 {
+        let color = ARGB16::new(
+            channel(pixel, self.alpha_mask),
+            channel(pixel, self.red_mask),
+            channel(pixel, self.green_mask),
+            channel(pixel, self.blue_mask),
+        );
+
+        if self.alpha_mask != 0 {
+            unpremultiply(color)
+        } else {
+            color
+        }
+    }
+}
+
+/// Resolves colormap indices into colors. Colormap entries have 16 bits per
+/// channel, which is kept as is.
+pub fn query_colors(
+    conn: &Connection,
+    colormap: xproto::Colormap,
+    pixels: &[u32],
+) -> Result<Vec<ARGB16>> {
+    let mut indices = pixels.to_vec();
+    indices.sort_unstable();
+    indices.dedup();
+
+    let reply = xproto::query_colors(conn, colormap, &indices).get_reply()?;
+    let colors: HashMap<u32, ARGB16> = indices
+        .into_iter()
+        .zip(reply.colors())
+        .map(|(index, rgb)| {
+            (
+                index,
+                ARGB16::new(0xffff, rgb.red(), rgb.green(), rgb.blue()),
+            )
+        })
+        .collect();
+
+    pixels
+        .iter()
+        .map(|index| {
+            colors
+                .get(index)
+                .copied()
+                .ok_or_else(|| anyhow!("Could not query colormap"))
+        })
+        .collect()
+}
+
+/// Extracts the channel selected by `mask` and scales it to 16 bits. Missing
+/// channels are at their maximum.
+fn channel(pixel: u32, mask: u32) -> u16 {
+    if mask == 0 {
+        return 0xffff;
+    }
+    let max = u64::from(mask >> mask.trailing_zeros());
+    let value = u64::from((pixel & mask) >> mask.trailing_zeros());
+    ((value * 0xffff + max / 2) / max) as u16
+}
+
+// Windows with an alpha channel store their colors premultiplied by alpha
+fn unpremultiply(color: ARGB16) -> ARGB16 {
+    if color.a == 0 {
+        return ARGB16::new(0, 0, 0, 0);
+    }
+    let channel = |n: u16| {
+        let n = (u32::from(n) * 0xffff + u32::from(color.a) / 2) / u32::from(color.a);
+        n.min(0xffff) as u16
+    };
+    ARGB16::new(
+        color.a,
+        channel(color.r),
+        channel(color.g),
+        channel(color.b),
+    )
+}
+
+#[test]
+fn test_unpremultiply() {
+    let transparent = ARGB16::new(0, 0, 0, 0);
+    assert_eq!(unpremultiply(transparent), transparent);
+
+    let white = ARGB16::new(0xffff, 0xffff, 0xffff, 0xffff);
+    assert_eq!(unpremultiply(white), white);
+
+    assert_eq!(
+        unpremultiply(ARGB16::new(0x8080, 0x8080, 0x4040, 0)),
+        ARGB16::new(0x8080, 0xffff, 0x8000, 0)
+    );
+    assert_eq!(
+        unpremultiply(ARGB16::new(0x3333, 0x3333, 0x3333, 0x4444)),
+        ARGB16::new(0x3333, 0xffff, 0xffff, 0xffff)
+    );
+}
+
+#[test]
+fn test_decode() {
+    let rgb = (0xff0000, 0x00ff00, 0x0000ff);
+    let bgr = (0x0000ff, 0x00ff00, 0xff0000);
+    let color = ARGB16::new(0xffff, 0x1212, 0x3434, 0x5656);
+
+    let format = PixelFormat::from_masks(24, 32, false, rgb).unwrap();
+    assert_eq!(format.bytes_per_pixel(), 4);
+    assert_eq!(format.decode(format.read(&[0x56, 0x34, 0x12, 0x00])), color);
+
+    let format = PixelFormat::from_masks(24, 32, true, rgb).unwrap();
+    assert_eq!(format.decode(format.read(&[0x00, 0x12, 0x34, 0x56])), color);
+
+    let format = PixelFormat::from_masks(24, 32, false, bgr).unwrap();
+    assert_eq!(format.decode(format.read(&[0x12, 0x34, 0x56, 0x00])), color);
+
+    let format = PixelFormat::from_masks(24, 24, true, bgr).unwrap();
+    assert_eq!(format.bytes_per_pixel(), 3);
+    assert_eq!(format.decode(format.read(&[0x56, 0x34, 0x12])), color);
+
+    let format = PixelFormat::from_masks(32, 32, false, rgb).unwrap();
+    assert_eq!(
+        format.decode(format.read(&[0x00, 0x40, 0x80, 0x80])),
+        ARGB16::new(0x8080, 0xffff, 0x8000, 0)
+    );
+
+    let rgb565 = (0xf800, 0x07e0, 0x001f);
+    let format = PixelFormat::from_masks(16, 16, false, rgb565).unwrap();
+    assert_eq!(
+        format.decode(format.read(&(0x1f << 11 | 0x20 << 5 | 0x01u16).to_le_bytes())),
+        ARGB16::new(0xffff, 0xffff, 0x8208, 0x0842)
+    );
+    let format = PixelFormat::from_masks(16, 16, true, rgb565).unwrap();
+    assert_eq!(
+        format.decode(format.read(&(0x1f << 11 | 0x20 << 5 | 0x01u16).to_be_bytes())),
+        ARGB16::new(0xffff, 0xffff, 0x8208, 0x0842)
+    );
+
+    let rgb101010 = (0x3ff0_0000, 0x000f_fc00, 0x0000_03ff);
+    let format = PixelFormat::from_masks(30, 32, false, rgb101010).unwrap();
+    assert_eq!(
+        format.decode(format.read(&(0x3ff << 20 | 0x200 << 10 | 0x001u32).to_le_bytes())),
+        ARGB16::new(0xffff, 0xffff, 0x8020, 0x0040)
+    );
+
+    assert!(PixelFormat::from_masks(1, 1, false, (0, 0, 0)).is_err());
+    assert!(PixelFormat::from_masks(4, 4, false, (0, 0, 0)).is_err());
+}
+
+#[test]
+fn test_indexed() {
+    let format = PixelFormat::from_indexed(8, false).unwrap();
+    assert!(format.is_indexed());
+    assert_eq!(format.bytes_per_pixel(), 1);
+    assert_eq!(format.read(&[0xa5]), 0xa5);
+
+    let format = PixelFormat::from_indexed(16, true).unwrap();
+    assert_eq!(format.read(&[0x01, 0x02]), 0x0102);
+
+    let format = PixelFormat::from_masks(24, 32, false, (0xff0000, 0x00ff00, 0x0000ff)).unwrap();
+    assert!(!format.is_indexed());
+
+    assert!(PixelFormat::from_indexed(4, false).is_err());
+}
+
+#[test]
+fn test_direct_color() {
+    let masks = (0xff0000, 0x00ff00, 0x0000ff);
+    let format = PixelFormat::from_direct(24, 32, false, masks).unwrap();
+    assert!(format.is_indexed());
+    assert_eq!(format.bytes_per_pixel(), 4);
+    let pixel = format.read(&[0x56, 0x34, 0x12, 0xff]);
+    assert_eq!(format.colormap_index(pixel), 0x123456);
+
+    let format = PixelFormat::from_indexed(8, false).unwrap();
+    assert_eq!(format.colormap_index(0xa5), 0xa5);
+}